@@ -1,12 +1,16 @@
 use std::{
     collections::HashSet,
-    sync::{Arc, LazyLock},
+    sync::{
+        Arc, LazyLock, OnceLock, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
 };
 
 use adbc_core::{
     Connection, Optionable,
     error::{Error, Result, Status},
-    options::{AdbcVersion, InfoCode, OptionConnection},
+    options::{AdbcVersion, InfoCode, OptionConnection, OptionValue},
     schemas,
 };
 use arrow_array::{RecordBatch, RecordBatchReader};
@@ -15,9 +19,15 @@ use arrow_schema::Schema;
 include!(concat!(env!("OUT_DIR"), "/deps_versions.rs"));
 
 use crate::{
+    consts::CONNECTION_OPTION_HOST,
+    pool::ConnectionPool,
     reader::SingleBatchReader,
     statement::ClickhouseStatement,
-    utils::{GetInfoBuilder, GetObjectsBuilder, NativeClientExt, Runtime, from_clickhouse_error},
+    utils::{
+        ArrowClientExt, CancelState, ClientFactory, GetInfoBuilder, GetObjectsBuilder,
+        QueryDetails, Runtime, SettingsMap, from_clickhouse_error, quote_table, result_schema,
+        setting_literal, setting_name,
+    },
 };
 
 static INFO_FIELDS: LazyLock<HashSet<InfoCode>> = LazyLock::new(|| {
@@ -33,30 +43,188 @@ static INFO_FIELDS: LazyLock<HashSet<InfoCode>> = LazyLock::new(|| {
     .collect()
 });
 
-pub struct ClickhouseConnection {
-    rt: Arc<Runtime>,
-    arrow_conn: clickhouse_arrow::ArrowClient,
-    native_conn: clickhouse_arrow::NativeClient,
-    clickhouse_version: String,
+/// The server side of a connection: everything that can be handed over to the next
+/// [`ClickhouseConnection`] when pooling is enabled.
+pub(crate) struct ClientSession {
+    pub arrow_conn: clickhouse_arrow::ArrowClient,
+    /// Only metadata queries need the native client, so it is opened on first use.
+    pub native_conn: Option<clickhouse_arrow::NativeClient>,
+    pub factory: Option<ClientFactory>,
+    pub clickhouse_version: String,
+    /// The `host:port` this session was opened against.
+    pub host: Option<String>,
 }
 
-impl ClickhouseConnection {
-    pub fn new(
-        rt: Arc<Runtime>,
+impl ClientSession {
+    pub async fn new(
         arrow_conn: clickhouse_arrow::ArrowClient,
-        native_conn: clickhouse_arrow::NativeClient,
+        factory: Option<ClientFactory>,
     ) -> Self {
-        let version = rt
-            .block_on(native_conn.fetch_version())
+        let version = arrow_conn
+            .fetch_version()
+            .await
             .ok()
             .flatten()
             .unwrap_or(DEP_CLICKHOUSE_ARROW_VERSION.to_string());
 
         Self {
-            rt,
             arrow_conn,
-            native_conn,
+            native_conn: None,
+            factory,
             clickhouse_version: version,
+            host: None,
+        }
+    }
+
+    pub(crate) async fn is_healthy(&self) -> bool {
+        if self.arrow_conn.health_check(true).await.is_err() {
+            return false;
+        }
+
+        match &self.native_conn {
+            Some(native_conn) => native_conn.health_check(true).await.is_ok(),
+            None => true,
+        }
+    }
+}
+
+pub struct ClickhouseConnection {
+    rt: Arc<Runtime>,
+    arrow_conn: clickhouse_arrow::ArrowClient,
+    native_conn: OnceLock<clickhouse_arrow::NativeClient>,
+    factory: Option<ClientFactory>,
+    clickhouse_version: String,
+    host: Option<String>,
+    settings: SettingsMap,
+    query_timeout: Option<Duration>,
+    /// Cancellation state of the statements created so far, for [`Connection::cancel`].
+    statements: Vec<Weak<CancelState>>,
+    pool: Option<Arc<ConnectionPool>>,
+    /// Set once the server session was changed in a way that must not leak into the next user of
+    /// a pooled session. Shared with the statements, which run `SET` or `USE` and create
+    /// temporary tables.
+    session_modified: Arc<AtomicBool>,
+}
+
+impl ClickhouseConnection {
+    pub(crate) fn from_session(rt: Arc<Runtime>, session: ClientSession) -> Self {
+        let native_conn = OnceLock::new();
+        if let Some(conn) = session.native_conn {
+            let _ = native_conn.set(conn);
+        }
+
+        Self {
+            rt,
+            arrow_conn: session.arrow_conn,
+            native_conn,
+            factory: session.factory,
+            clickhouse_version: session.clickhouse_version,
+            host: session.host,
+            settings: SettingsMap::new(),
+            query_timeout: None,
+            statements: vec![],
+            pool: None,
+            session_modified: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub(crate) fn with_pool(mut self, pool: Arc<ConnectionPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Default `clickhouse.query_timeout_ms` of the statements created by this connection.
+    pub(crate) fn with_query_timeout(mut self, query_timeout: Option<Duration>) -> Self {
+        self.query_timeout = query_timeout;
+        self
+    }
+
+    /// Opens the native client on the first metadata call, replaying the connection level
+    /// settings so it sees the same session state as the arrow client.
+    fn native_conn(&self) -> Result<&clickhouse_arrow::NativeClient> {
+        if let Some(conn) = self.native_conn.get() {
+            return Ok(conn);
+        }
+
+        let factory = self.factory.as_ref().ok_or_else(|| {
+            Error::with_message_and_status(
+                "[Clickhouse] Native client is not available on this connection",
+                Status::InvalidState,
+            )
+        })?;
+
+        let conn = self.rt.block_on(async {
+            let conn = factory.build_native().await.map_err(|err| {
+                let details = QueryDetails {
+                    endpoint: self.host.clone(),
+                    ..Default::default()
+                };
+                details.attach(from_clickhouse_error(
+                    "Failed to create native clickhouse client",
+                    err,
+                ))
+            })?;
+            for (name, value) in &self.settings {
+                let sql = format!("SET {name} = {}", setting_literal(value));
+                conn.execute(&sql, None).await.map_err(|err| {
+                    QueryDetails::new(&sql, self.host.as_deref()).attach(from_clickhouse_error(
+                        "Failed to create native clickhouse client",
+                        err,
+                    ))
+                })?;
+            }
+            Ok::<_, Error>(conn)
+        })?;
+
+        Ok(self.native_conn.get_or_init(|| conn))
+    }
+
+    /// Connection level settings live in the server session, so they apply to every query issued
+    /// on this connection, including the metadata queries of the native client.
+    fn set_setting(&mut self, name: &str, value: String) -> Result<()> {
+        let sql = format!("SET {name} = {}", setting_literal(&value));
+
+        self.rt
+            .block_on(async {
+                self.arrow_conn.execute(&sql, None).await?;
+                match self.native_conn.get() {
+                    Some(native_conn) => native_conn.execute(&sql, None).await,
+                    None => Ok(()),
+                }
+            })
+            .map_err(|err| {
+                QueryDetails::new(&sql, self.host.as_deref())
+                    .attach(from_clickhouse_error(format!("Failed to set {name}"), err))
+            })?;
+
+        self.session_modified.store(true, Ordering::Relaxed);
+        self.settings.insert(name.to_string(), value);
+        Ok(())
+    }
+}
+
+impl Drop for ClickhouseConnection {
+    fn drop(&mut self) {
+        let Some(pool) = self.pool.take() else {
+            return;
+        };
+
+        // Statements and their readers outliving the connection keep using its client, so the
+        // session cannot go to another connection either.
+        let in_use = self
+            .statements
+            .iter()
+            .any(|statement| statement.strong_count() > 0);
+        if in_use || self.session_modified.load(Ordering::Relaxed) {
+            pool.discard();
+        } else {
+            pool.release(ClientSession {
+                arrow_conn: self.arrow_conn.clone(),
+                native_conn: self.native_conn.take(),
+                factory: self.factory.clone(),
+                clickhouse_version: self.clickhouse_version.clone(),
+                host: self.host.take(),
+            });
         }
     }
 }
@@ -67,8 +235,21 @@ impl Optionable for ClickhouseConnection {
     fn set_option(
         &mut self,
         key: Self::Option,
-        _value: adbc_core::options::OptionValue,
+        value: adbc_core::options::OptionValue,
     ) -> Result<()> {
+        if let OptionConnection::Other(name) = &key
+            && let Some(name) = setting_name(name)
+        {
+            let name = name?;
+            return match value {
+                OptionValue::String(value) => self.set_setting(name, value),
+                _ => Err(Error::with_message_and_status(
+                    format!("[Clickhouse] Value is not a string, key: {key:?}"),
+                    Status::InvalidArguments,
+                )),
+            };
+        }
+
         Err(Error::with_message_and_status(
             format!("[Clickhouse] Unrecognized option: {key:?}"),
             Status::NotFound,
@@ -76,10 +257,24 @@ impl Optionable for ClickhouseConnection {
     }
 
     fn get_option_string(&self, key: Self::Option) -> Result<String> {
-        Err(Error::with_message_and_status(
-            format!("[Clickhouse] Unrecognized option: {key:?}"),
-            Status::NotFound,
-        ))
+        if let OptionConnection::Other(name) = &key
+            && let Some(name) = setting_name(name)
+            && let Some(value) = self.settings.get(name?)
+        {
+            return Ok(value.clone());
+        }
+
+        match key {
+            OptionConnection::Other(key)
+                if key == CONNECTION_OPTION_HOST && self.host.is_some() =>
+            {
+                Ok(self.host.clone().unwrap())
+            }
+            _ => Err(Error::with_message_and_status(
+                format!("[Clickhouse] Unrecognized option: {key:?}"),
+                Status::NotFound,
+            )),
+        }
     }
 
     fn get_option_bytes(&self, key: Self::Option) -> Result<Vec<u8>> {
@@ -108,17 +303,41 @@ impl Connection for ClickhouseConnection {
     type StatementType = ClickhouseStatement;
 
     fn new_statement(&mut self) -> Result<Self::StatementType> {
-        Ok(ClickhouseStatement::new(
-            self.rt.clone(),
-            self.arrow_conn.clone(),
-        ))
+        let cancel = Arc::new(CancelState::new(
+            Some(self.arrow_conn.clone()),
+            self.factory.clone(),
+        ));
+        self.statements
+            .retain(|statement| statement.strong_count() > 0);
+        self.statements.push(Arc::downgrade(&cancel));
+
+        Ok(
+            ClickhouseStatement::new(self.rt.clone(), self.arrow_conn.clone())
+                .with_factory(self.factory.clone().map(|factory| {
+                    self.settings
+                        .iter()
+                        .fold(factory, |factory, (name, value)| {
+                            factory.with_setting(name.clone(), value.clone())
+                        })
+                }))
+                .with_query_timeout(self.query_timeout)
+                .with_cancel_state(cancel)
+                .with_host(self.host.clone())
+                .with_session_modified(self.session_modified.clone()),
+        )
     }
 
+    /// Cancels the running queries of every statement of this connection.
     fn cancel(&mut self) -> Result<()> {
-        Err(Error::with_message_and_status(
-            "[Clickhouse] Cancel not implemented".to_string(),
-            Status::Internal,
-        ))
+        let mut result = Ok(());
+
+        for statement in self.statements.iter().filter_map(Weak::upgrade) {
+            if let Err(err) = statement.cancel(&self.rt) {
+                result = Err(err);
+            }
+        }
+
+        result
     }
 
     fn get_info(
@@ -163,32 +382,31 @@ impl Connection for ClickhouseConnection {
     ) -> Result<impl RecordBatchReader + Send> {
         let builder =
             GetObjectsBuilder::new(catalog, db_schema, table_name, table_type, column_name);
-        let batch = self.rt.block_on(builder.build(&self.native_conn, &depth))?;
+        let native_conn = self.native_conn()?;
+        let builder = builder.with_endpoint(self.host.as_deref());
+        let batch = self.rt.block_on(builder.build(native_conn, &depth))?;
 
         let reader = SingleBatchReader::new(batch);
         Ok(reader)
     }
 
+    /// ClickHouse has no catalogs, a catalog names a database just like a db_schema.
     fn get_table_schema(
         &self,
-        _catalog: Option<&str>,
+        catalog: Option<&str>,
         db_schema: Option<&str>,
         table_name: &str,
     ) -> Result<Schema> {
-        let schema = self
+        let table = quote_table(db_schema.or(catalog), table_name);
+        let columns = self
             .rt
-            .block_on(self.arrow_conn.fetch_schema(db_schema, &[table_name], None))
-            .map(|schemas| {
-                schemas.get(table_name).cloned().ok_or_else(|| {
-                    Error::with_message_and_status(
-                        format!("Failed to get schema for {table_name}"),
-                        Status::Internal,
-                    )
-                })
-            })
-            .map_err(|err| from_clickhouse_error("Failed to fetch table schema", err))??;
+            .block_on(self.arrow_conn.describe_table(&table, None))
+            .map_err(|err| {
+                QueryDetails::new(&format!("DESCRIBE TABLE {table}"), self.host.as_deref())
+                    .attach(from_clickhouse_error("Failed to fetch table schema", err))
+            })?;
 
-        Ok((*schema).clone())
+        result_schema(columns)
     }
 
     fn get_table_types(&self) -> Result<impl RecordBatchReader + Send> {
@@ -1 +1,57 @@
 pub const DATABASE_OPTION_SCHEMA: &str = "clickhouse.schema";
+pub const DATABASE_OPTION_COMPRESSION: &str = "clickhouse.compression";
+/// One of `in_order`, `random` or `round_robin`, used when the uri lists several hosts.
+pub const DATABASE_OPTION_LOAD_BALANCING: &str = "clickhouse.load_balancing";
+/// How long to wait for each host before trying the next one. Zero waits forever.
+pub const DATABASE_OPTION_CONNECT_TIMEOUT_MS: &str = "clickhouse.connect_timeout_ms";
+/// Client side deadline for a whole query, including reading its results. Also sent to the server
+/// as `max_execution_time` with every query. Zero waits forever. Accepted by the database and the
+/// statement.
+pub const OPTION_QUERY_TIMEOUT_MS: &str = "clickhouse.query_timeout_ms";
+pub const DATABASE_OPTION_RUNTIME_WORKER_THREADS: &str = "clickhouse.runtime.worker_threads";
+
+pub const DATABASE_OPTION_POOL_ENABLED: &str = "clickhouse.pool.enabled";
+pub const DATABASE_OPTION_POOL_MIN_SIZE: &str = "clickhouse.pool.min_size";
+pub const DATABASE_OPTION_POOL_MAX_SIZE: &str = "clickhouse.pool.max_size";
+/// Zero keeps idle connections open forever.
+pub const DATABASE_OPTION_POOL_IDLE_TIMEOUT_MS: &str = "clickhouse.pool.idle_timeout_ms";
+pub const DATABASE_OPTION_POOL_HEALTH_CHECK: &str = "clickhouse.pool.health_check";
+pub const DATABASE_OPTION_POOL_ACQUIRE_TIMEOUT_MS: &str = "clickhouse.pool.acquire_timeout_ms";
+
+pub const DATABASE_OPTION_TLS_ENABLED: &str = "clickhouse.tls.enabled";
+/// Host name the server certificate is verified against, the host of the endpoint by default.
+pub const DATABASE_OPTION_TLS_SERVER_NAME: &str = "clickhouse.tls.server_name";
+/// PEM file of the certificates the server is verified against instead of the webpki roots.
+pub const DATABASE_OPTION_TLS_CA_FILE: &str = "clickhouse.tls.ca_file";
+/// PEM files of the client certificate chain and its private key, for mutual TLS.
+pub const DATABASE_OPTION_TLS_CLIENT_CERT: &str = "clickhouse.tls.client_cert";
+pub const DATABASE_OPTION_TLS_CLIENT_KEY: &str = "clickhouse.tls.client_key";
+/// Accept any server certificate, for testing only.
+pub const DATABASE_OPTION_TLS_INSECURE_SKIP_VERIFY: &str = "clickhouse.tls.insecure_skip_verify";
+
+/// Read only, the `host:port` a connection ended up on.
+pub const CONNECTION_OPTION_HOST: &str = "clickhouse.host";
+
+/// Prefix of the options that pass ClickHouse settings through, e.g.
+/// `clickhouse.setting.max_threads`. Accepted by the database, connection and statement.
+/// Statement settings are sent along with each of the statement's queries.
+pub const SETTING_OPTION_PREFIX: &str = "clickhouse.setting.";
+
+/// Table engine of the tables bulk ingest creates, `MergeTree` by default.
+pub const STATEMENT_OPTION_INGEST_ENGINE: &str = "clickhouse.ingest.engine";
+/// `ORDER BY` expression of the tables bulk ingest creates, `tuple()` for `MergeTree` engines.
+pub const STATEMENT_OPTION_INGEST_ORDER_BY: &str = "clickhouse.ingest.order_by";
+/// `PARTITION BY` expression of the tables bulk ingest creates.
+pub const STATEMENT_OPTION_INGEST_PARTITION_BY: &str = "clickhouse.ingest.partition_by";
+/// `TTL` expression of the tables bulk ingest creates.
+pub const STATEMENT_OPTION_INGEST_TTL: &str = "clickhouse.ingest.ttl";
+/// Bulk ingest concatenates bound batches into blocks of at least this many rows, splitting larger
+/// ones. Zero, the default, sends batches as they are bound.
+pub const STATEMENT_OPTION_INGEST_MIN_BLOCK_ROWS: &str = "clickhouse.ingest.min_block_rows";
+/// Like `clickhouse.ingest.min_block_rows`, in bytes of Arrow memory. A block is complete once
+/// either limit is reached.
+pub const STATEMENT_OPTION_INGEST_MIN_BLOCK_BYTES: &str = "clickhouse.ingest.min_block_bytes";
+/// Number of connections bulk ingest inserts over concurrently, each streaming its share of the
+/// blocks into a single INSERT. One, the default, sends everything through the statement's
+/// connection.
+pub const STATEMENT_OPTION_INGEST_PARALLELISM: &str = "clickhouse.ingest.parallelism";
@@ -1,23 +1,323 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, Mutex, atomic::AtomicUsize},
+    time::Duration,
+};
 
 use adbc_core::{
     Database, Optionable,
     error::{Error, Result, Status},
     options::{OptionDatabase, OptionValue},
 };
+use clickhouse_arrow::CompressionMethod;
 
 use crate::{
-    connection::ClickhouseConnection,
-    consts::DATABASE_OPTION_SCHEMA,
-    utils::{Runtime, from_clickhouse_error},
+    connection::{ClickhouseConnection, ClientSession},
+    consts::{
+        DATABASE_OPTION_COMPRESSION, DATABASE_OPTION_CONNECT_TIMEOUT_MS,
+        DATABASE_OPTION_LOAD_BALANCING, DATABASE_OPTION_POOL_ACQUIRE_TIMEOUT_MS,
+        DATABASE_OPTION_POOL_ENABLED, DATABASE_OPTION_POOL_HEALTH_CHECK,
+        DATABASE_OPTION_POOL_IDLE_TIMEOUT_MS, DATABASE_OPTION_POOL_MAX_SIZE,
+        DATABASE_OPTION_POOL_MIN_SIZE, DATABASE_OPTION_RUNTIME_WORKER_THREADS,
+        DATABASE_OPTION_SCHEMA, DATABASE_OPTION_TLS_CA_FILE, DATABASE_OPTION_TLS_CLIENT_CERT,
+        DATABASE_OPTION_TLS_CLIENT_KEY, DATABASE_OPTION_TLS_ENABLED,
+        DATABASE_OPTION_TLS_INSECURE_SKIP_VERIFY, DATABASE_OPTION_TLS_SERVER_NAME,
+        OPTION_QUERY_TIMEOUT_MS, SETTING_OPTION_PREFIX,
+    },
+    pool::{ConnectionPool, PoolOptions},
+    utils::{
+        ClientFactory, ConnectionUri, LoadBalancing, Runtime, SettingsMap, TlsOptions,
+        arrow_options, from_clickhouse_error, parse_bool_option, parse_compression,
+        parse_duration_option, parse_number_option, push_error_detail, setting_name,
+        timeout_millis,
+    },
 };
 
+/// Options set explicitly (username, password, `clickhouse.schema`, `clickhouse.compression`,
+/// `clickhouse.tls.enabled`, `clickhouse.connect_timeout_ms`, `clickhouse.query_timeout_ms`,
+/// `clickhouse.setting.*`) always take precedence over the values parsed from the uri, regardless
+/// of the order in which they were set.
 #[derive(Default)]
 pub struct ClickhouseDatabase {
     uri: Option<String>,
+    parsed_uri: Option<ConnectionUri>,
     username: Option<String>,
     password: Option<String>,
     schema: Option<String>,
+    compression: Option<CompressionMethod>,
+    tls: TlsOptions,
+    settings: SettingsMap,
+    load_balancing: LoadBalancing,
+    /// Counts connection attempts, drives `random` and `round_robin` load balancing.
+    next_host: AtomicUsize,
+    /// Zero when explicitly disabled, see [`Self::connect_timeout`].
+    connect_timeout: Option<Duration>,
+    query_timeout: Option<Duration>,
+    worker_threads: Option<usize>,
+    runtime: Mutex<Option<Arc<Runtime>>>,
+    pool_options: PoolOptions,
+    pool: Mutex<Option<Arc<ConnectionPool>>>,
+}
+
+impl ClickhouseDatabase {
+    /// Runs all connections of this database on an existing tokio runtime instead of the one the
+    /// driver would otherwise create on the first [`Database::new_connection`].
+    pub fn with_runtime_handle(self, handle: tokio::runtime::Handle) -> Self {
+        *self.runtime.lock().unwrap() = Some(Arc::new(Runtime::Handle(handle)));
+        self
+    }
+
+    /// The runtime is created lazily and shared by every connection of this database.
+    fn runtime(&self) -> Result<Arc<Runtime>> {
+        let mut runtime = self.runtime.lock().unwrap();
+
+        if let Some(rt) = runtime.as_ref() {
+            return Ok(rt.clone());
+        }
+
+        let rt = Arc::new(Runtime::new(self.worker_threads).map_err(|err| {
+            Error::with_message_and_status(
+                format!("[Clickhouse] Failed to create tokio runtime: {err}"),
+                Status::Internal,
+            )
+        })?);
+        *runtime = Some(rt.clone());
+
+        Ok(rt)
+    }
+
+    /// The pool is created on the first [`Database::new_connection`] when `clickhouse.pool.enabled`
+    /// is set, and opens `clickhouse.pool.min_size` connections right away.
+    fn pool(&self, rt: &Runtime) -> Result<Option<Arc<ConnectionPool>>> {
+        if !self.pool_options.enabled {
+            return Ok(None);
+        }
+
+        let mut pool = self.pool.lock().unwrap();
+
+        if let Some(pool) = pool.as_ref() {
+            return Ok(Some(pool.clone()));
+        }
+
+        let new_pool = Arc::new(ConnectionPool::new(self.pool_options.clone()));
+        *pool = Some(new_pool.clone());
+        drop(pool);
+
+        // A pool that could not open its minimum is dropped, the next connection tries again.
+        if let Err(err) = new_pool.fill(|| self.connect(rt)) {
+            let mut pool = self.pool.lock().unwrap();
+            if pool
+                .as_ref()
+                .is_some_and(|pool| Arc::ptr_eq(pool, &new_pool))
+            {
+                *pool = None;
+            }
+            return Err(err);
+        }
+        new_pool.start_eviction(rt);
+
+        Ok(Some(new_pool))
+    }
+
+    fn connect_timeout(&self) -> Option<Duration> {
+        let uri = self.parsed_uri.as_ref().and_then(|uri| uri.connect_timeout);
+        self.connect_timeout.or(uri).filter(|v| !v.is_zero())
+    }
+
+    fn query_timeout(&self) -> Option<Duration> {
+        let uri = self.parsed_uri.as_ref().and_then(|uri| uri.query_timeout);
+        self.query_timeout.or(uri).filter(|v| !v.is_zero())
+    }
+
+    fn tls_options(&self) -> TlsOptions {
+        let mut tls = self.tls.clone();
+        tls.enabled = tls
+            .enabled
+            .or(self.parsed_uri.as_ref().and_then(|uri| uri.tls));
+        tls
+    }
+
+    /// Tries the hosts of the uri in the order given by `clickhouse.load_balancing` until one
+    /// accepts the connection, giving each of them `clickhouse.connect_timeout_ms`.
+    fn connect(&self, rt: &Runtime) -> Result<ClientSession> {
+        let Some(uri) = self.parsed_uri.as_ref() else {
+            return Err(Error::with_message_and_status(
+                "[Clickhouse] Uri must be set",
+                Status::InvalidArguments,
+            ));
+        };
+
+        let mut errors = vec![];
+        for idx in self
+            .load_balancing
+            .order(uri.endpoints.len(), &self.next_host)
+        {
+            let endpoint = &uri.endpoints[idx];
+            match self.connect_to(rt, endpoint) {
+                Ok(session) => return Ok(session),
+                Err(err) if uri.endpoints.len() == 1 => return Err(err),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        let status = errors.last().map_or(Status::IO, |err| err.status);
+        let mut details = vec![];
+        let message = errors
+            .into_iter()
+            .map(|err| {
+                details.extend(err.details.unwrap_or_default());
+                err.message.trim_start_matches("[Clickhouse] ").to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        let mut error = Error::with_message_and_status(
+            format!("[Clickhouse] Failed to connect to any host: {message}"),
+            status,
+        );
+        error.details = Some(details);
+        Err(error)
+    }
+
+    fn connect_to(&self, rt: &Runtime, endpoint: &str) -> Result<ClientSession> {
+        let factory = self.client_factory(endpoint)?;
+        let connect = async move {
+            let arrow_conn = factory.build_arrow().await.map_err(|err| {
+                from_clickhouse_error(
+                    format!("Failed to create arrow clickhouse client for {endpoint}"),
+                    err,
+                )
+            })?;
+
+            // Reading the server version is part of connecting, a server hanging after the
+            // handshake must not block the connection forever.
+            Ok(ClientSession::new(arrow_conn, Some(factory)).await)
+        };
+        let session = rt
+            .block_on(self.with_connect_timeout(endpoint, connect))
+            .map_err(|mut err| {
+                push_error_detail(&mut err, "clickhouse.endpoint", endpoint);
+                err
+            })?;
+
+        Ok(ClientSession {
+            host: Some(endpoint.to_string()),
+            ..session
+        })
+    }
+
+    /// Fails with `Status::Timeout` once `fut` takes longer than `clickhouse.connect_timeout_ms`.
+    async fn with_connect_timeout<T>(
+        &self,
+        endpoint: &str,
+        fut: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        match self.connect_timeout() {
+            Some(connect_timeout) => tokio::time::timeout(connect_timeout, fut)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(Error::with_message_and_status(
+                        format!(
+                            "[Clickhouse] Timed out connecting to {endpoint} after {}ms",
+                            connect_timeout.as_millis()
+                        ),
+                        Status::Timeout,
+                    ))
+                }),
+            None => fut.await,
+        }
+    }
+
+    /// Client options of `endpoint`, explicitly set options taking precedence over the uri.
+    fn client_factory(&self, endpoint: &str) -> Result<ClientFactory> {
+        let uri = self.parsed_uri.clone().unwrap_or_default();
+        let tls = self.tls_options();
+
+        let builder = clickhouse_arrow::ClientBuilder::new()
+            .with_endpoint(endpoint)
+            .with_database("default")
+            .with_arrow_options(arrow_options())
+            .with_tls(tls.is_enabled());
+
+        let builder = match &tls.server_name {
+            Some(server_name) => builder.with_domain(server_name),
+            None => builder,
+        };
+
+        let builder = match tls.client_config()? {
+            Some(tls_config) => builder.with_tls_config(tls_config),
+            None => builder,
+        };
+
+        let builder = if let Some(username) = self.username.clone().or(uri.username) {
+            builder.with_username(username)
+        } else {
+            builder
+        };
+
+        let builder = if let Some(password) = self.password.clone().or(uri.password) {
+            builder.with_password(password)
+        } else {
+            builder
+        };
+
+        let builder = if let Some(schema) = self.schema.clone().or(uri.database) {
+            builder.with_database(schema)
+        } else {
+            builder
+        };
+
+        let builder = if let Some(compression) = self.compression.or(uri.compression) {
+            builder.with_compression(compression)
+        } else {
+            builder
+        };
+
+        let settings = uri
+            .settings
+            .into_iter()
+            .chain(self.settings.clone())
+            .collect::<SettingsMap>();
+        let builder = settings
+            .into_iter()
+            .fold(builder, |builder, (name, value)| {
+                builder.with_setting(name, value)
+            });
+
+        Ok(ClientFactory::new(builder))
+    }
+}
+
+/// Options holding numbers, which may also be set and read as `OptionValue::Int` or
+/// `OptionValue::Double`. Settings are included as most of them are numeric.
+fn accepts_numbers(key: &OptionDatabase) -> bool {
+    let OptionDatabase::Other(key) = key else {
+        return false;
+    };
+
+    key.starts_with(SETTING_OPTION_PREFIX)
+        || [
+            DATABASE_OPTION_CONNECT_TIMEOUT_MS,
+            OPTION_QUERY_TIMEOUT_MS,
+            DATABASE_OPTION_RUNTIME_WORKER_THREADS,
+            DATABASE_OPTION_POOL_MIN_SIZE,
+            DATABASE_OPTION_POOL_MAX_SIZE,
+            DATABASE_OPTION_POOL_IDLE_TIMEOUT_MS,
+            DATABASE_OPTION_POOL_ACQUIRE_TIMEOUT_MS,
+        ]
+        .contains(&key.as_str())
+}
+
+/// Options of the pool itself and those its sessions were opened with, which would otherwise only
+/// apply to some of the connections handed out once the pool exists.
+fn fixed_by_pool(key: &OptionDatabase) -> bool {
+    let OptionDatabase::Other(key) = key else {
+        return true;
+    };
+
+    key.starts_with("clickhouse.pool.")
+        || key.starts_with("clickhouse.tls.")
+        || key.starts_with(SETTING_OPTION_PREFIX)
+        || [DATABASE_OPTION_SCHEMA, DATABASE_OPTION_COMPRESSION].contains(&key.as_str())
 }
 
 impl Optionable for ClickhouseDatabase {
@@ -28,8 +328,21 @@ impl Optionable for ClickhouseDatabase {
         key: Self::Option,
         value: adbc_core::options::OptionValue,
     ) -> Result<()> {
+        if let OptionDatabase::Other(name) = &key {
+            if name == DATABASE_OPTION_CONNECT_TIMEOUT_MS {
+                self.connect_timeout = Some(parse_duration_option(name, value)?);
+                return Ok(());
+            }
+            if name == OPTION_QUERY_TIMEOUT_MS {
+                self.query_timeout = Some(parse_duration_option(name, value)?);
+                return Ok(());
+            }
+        }
+
         let value = match value {
             OptionValue::String(value) => value,
+            OptionValue::Int(value) if accepts_numbers(&key) => value.to_string(),
+            OptionValue::Double(value) if accepts_numbers(&key) => value.to_string(),
             _ => {
                 return Err(Error::with_message_and_status(
                     format!("[Clickhouse] Value is not a string, key: {key:?}"),
@@ -38,13 +351,101 @@ impl Optionable for ClickhouseDatabase {
             }
         };
 
+        if fixed_by_pool(&key) && self.pool.lock().unwrap().is_some() {
+            return Err(Error::with_message_and_status(
+                format!(
+                    "[Clickhouse] {} cannot be changed once the pool is created",
+                    key.as_ref()
+                ),
+                Status::InvalidState,
+            ));
+        }
+
+        if let OptionDatabase::Other(key) = &key
+            && let Some(name) = setting_name(key)
+        {
+            self.settings.insert(name?.to_string(), value);
+            return Ok(());
+        }
+
         match key {
-            OptionDatabase::Uri => self.uri = Some(value),
+            OptionDatabase::Uri => {
+                self.parsed_uri = Some(ConnectionUri::parse(&value)?);
+                self.uri = Some(value);
+            }
             OptionDatabase::Username => self.username = Some(value),
             OptionDatabase::Password => self.password = Some(value),
             OptionDatabase::Other(key) if key == DATABASE_OPTION_SCHEMA => {
                 self.schema = Some(value);
             }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_COMPRESSION => {
+                self.compression = Some(parse_compression(&value)?);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_LOAD_BALANCING => {
+                self.load_balancing = LoadBalancing::parse(&value)?;
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_RUNTIME_WORKER_THREADS => {
+                if self.runtime.lock().unwrap().is_some() {
+                    return Err(Error::with_message_and_status(
+                        format!("[Clickhouse] {key} cannot be changed once the runtime is created"),
+                        Status::InvalidState,
+                    ));
+                }
+
+                let worker_threads = value.parse::<usize>().ok().filter(|v| *v > 0);
+                self.worker_threads = Some(worker_threads.ok_or_else(|| {
+                    Error::with_message_and_status(
+                        format!("[Clickhouse] Invalid value for {key}: {value}"),
+                        Status::InvalidArguments,
+                    )
+                })?);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_ENABLED => {
+                self.pool_options.enabled = parse_bool_option(&key, &value)?;
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_MIN_SIZE => {
+                self.pool_options.min_size = parse_number_option(&key, &value)?;
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_MAX_SIZE => {
+                let max_size = parse_number_option(&key, &value)?;
+                if max_size == 0 {
+                    return Err(Error::with_message_and_status(
+                        format!("[Clickhouse] {key} must be greater than zero"),
+                        Status::InvalidArguments,
+                    ));
+                }
+                self.pool_options.max_size = max_size;
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_IDLE_TIMEOUT_MS => {
+                let idle_timeout = parse_number_option::<u64>(&key, &value)?;
+                self.pool_options.idle_timeout =
+                    Some(Duration::from_millis(idle_timeout)).filter(|v| !v.is_zero());
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_HEALTH_CHECK => {
+                self.pool_options.health_check = parse_bool_option(&key, &value)?;
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_ACQUIRE_TIMEOUT_MS => {
+                self.pool_options.acquire_timeout =
+                    Duration::from_millis(parse_number_option(&key, &value)?);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_ENABLED => {
+                self.tls.enabled = Some(parse_bool_option(&key, &value)?);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_SERVER_NAME => {
+                self.tls.server_name = Some(value);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_CA_FILE => {
+                self.tls.ca_file = Some(value);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_CLIENT_CERT => {
+                self.tls.client_cert = Some(value);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_CLIENT_KEY => {
+                self.tls.client_key = Some(value);
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_INSECURE_SKIP_VERIFY => {
+                self.tls.insecure_skip_verify = parse_bool_option(&key, &value)?;
+            }
             _ => {
                 return Err(Error::with_message_and_status(
                     format!("[Clickhouse] Unrecognized option: {key:?}"),
@@ -57,6 +458,13 @@ impl Optionable for ClickhouseDatabase {
     }
 
     fn get_option_string(&self, key: Self::Option) -> Result<String> {
+        if let OptionDatabase::Other(name) = &key
+            && let Some(name) = setting_name(name)
+            && let Some(value) = self.settings.get(name?)
+        {
+            return Ok(value.clone());
+        }
+
         match key {
             OptionDatabase::Uri if self.uri.is_some() => Ok(self.uri.clone().unwrap()),
             OptionDatabase::Username if self.username.is_some() => {
@@ -70,6 +478,72 @@ impl Optionable for ClickhouseDatabase {
             {
                 Ok(self.schema.clone().unwrap())
             }
+            OptionDatabase::Other(key)
+                if key == DATABASE_OPTION_COMPRESSION && self.compression.is_some() =>
+            {
+                Ok(self.compression.unwrap().to_string().to_lowercase())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_LOAD_BALANCING => {
+                Ok(self.load_balancing.to_string())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_CONNECT_TIMEOUT_MS => {
+                Ok(timeout_millis(self.connect_timeout()).to_string())
+            }
+            OptionDatabase::Other(key) if key == OPTION_QUERY_TIMEOUT_MS => {
+                Ok(timeout_millis(self.query_timeout()).to_string())
+            }
+            OptionDatabase::Other(key)
+                if key == DATABASE_OPTION_RUNTIME_WORKER_THREADS
+                    && self.worker_threads.is_some() =>
+            {
+                Ok(self.worker_threads.unwrap().to_string())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_ENABLED => {
+                Ok(self.pool_options.enabled.to_string())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_MIN_SIZE => {
+                Ok(self.pool_options.min_size.to_string())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_MAX_SIZE => {
+                Ok(self.pool_options.max_size.to_string())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_IDLE_TIMEOUT_MS => Ok(self
+                .pool_options
+                .idle_timeout
+                .map_or(0, |v| v.as_millis())
+                .to_string()),
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_HEALTH_CHECK => {
+                Ok(self.pool_options.health_check.to_string())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_POOL_ACQUIRE_TIMEOUT_MS => {
+                Ok(self.pool_options.acquire_timeout.as_millis().to_string())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_ENABLED => {
+                Ok(self.tls_options().is_enabled().to_string())
+            }
+            OptionDatabase::Other(key)
+                if key == DATABASE_OPTION_TLS_SERVER_NAME && self.tls.server_name.is_some() =>
+            {
+                Ok(self.tls.server_name.clone().unwrap())
+            }
+            OptionDatabase::Other(key)
+                if key == DATABASE_OPTION_TLS_CA_FILE && self.tls.ca_file.is_some() =>
+            {
+                Ok(self.tls.ca_file.clone().unwrap())
+            }
+            OptionDatabase::Other(key)
+                if key == DATABASE_OPTION_TLS_CLIENT_CERT && self.tls.client_cert.is_some() =>
+            {
+                Ok(self.tls.client_cert.clone().unwrap())
+            }
+            OptionDatabase::Other(key)
+                if key == DATABASE_OPTION_TLS_CLIENT_KEY && self.tls.client_key.is_some() =>
+            {
+                Ok(self.tls.client_key.clone().unwrap())
+            }
+            OptionDatabase::Other(key) if key == DATABASE_OPTION_TLS_INSECURE_SKIP_VERIFY => {
+                Ok(self.tls.insecure_skip_verify.to_string())
+            }
             _ => Err(Error::with_message_and_status(
                 format!("[Clickhouse] Unrecognized option: {key:?}"),
                 Status::NotFound,
@@ -85,17 +559,37 @@ impl Optionable for ClickhouseDatabase {
     }
 
     fn get_option_int(&self, key: Self::Option) -> Result<i64> {
-        Err(Error::with_message_and_status(
-            format!("[Clickhouse] Unrecognized option: {key:?}"),
-            Status::NotFound,
-        ))
+        if !accepts_numbers(&key) {
+            return Err(Error::with_message_and_status(
+                format!("[Clickhouse] Unrecognized option: {key:?}"),
+                Status::NotFound,
+            ));
+        }
+
+        let value = self.get_option_string(key.clone())?;
+        value.parse().map_err(|_| {
+            Error::with_message_and_status(
+                format!("[Clickhouse] Value of {key:?} is not an integer: {value}"),
+                Status::InvalidArguments,
+            )
+        })
     }
 
     fn get_option_double(&self, key: Self::Option) -> Result<f64> {
-        Err(Error::with_message_and_status(
-            format!("[Clickhouse] Unrecognized option: {key:?}"),
-            Status::NotFound,
-        ))
+        if !accepts_numbers(&key) {
+            return Err(Error::with_message_and_status(
+                format!("[Clickhouse] Unrecognized option: {key:?}"),
+                Status::NotFound,
+            ));
+        }
+
+        let value = self.get_option_string(key.clone())?;
+        value.parse().map_err(|_| {
+            Error::with_message_and_status(
+                format!("[Clickhouse] Value of {key:?} is not a number: {value}"),
+                Status::InvalidArguments,
+            )
+        })
     }
 }
 
@@ -103,65 +597,21 @@ impl Database for ClickhouseDatabase {
     type ConnectionType = ClickhouseConnection;
 
     fn new_connection(&self) -> Result<Self::ConnectionType> {
-        let rt = Runtime::new().map_err(|err| {
-            Error::with_message_and_status(
-                format!("[Clickhouse] Failed to create tokio runtime: {err}"),
-                Status::Internal,
-            )
-        })?;
-
-        let uri = self.uri.clone();
-        let username = self.username.clone();
-        let password = self.password.clone();
-        let schema = self.schema.clone();
-
-        let builder = clickhouse_arrow::ClientBuilder::new().with_database("default");
-
-        let builder = if let Some(uri) = uri {
-            builder.with_endpoint(uri)
-        } else {
-            builder
-        };
-
-        let builder = if let Some(username) = username {
-            builder.with_username(username)
-        } else {
-            builder
-        };
-
-        let builder = if let Some(password) = password {
-            builder.with_password(password)
-        } else {
-            builder
-        };
-
-        let builder = if let Some(schema) = schema {
-            builder.with_database(schema)
-        } else {
-            builder
-        };
-
-        let arrow_builder = builder.clone();
-        let arrow_conn = rt
-            .block_on(async move { arrow_builder.build_arrow().await })
-            .map_err(|err| {
-                from_clickhouse_error("[Clickhouse] Failed to create arrow clickhouse client", err)
-            })?;
-
-        let native_conn = rt
-            .block_on(async move { builder.build_native().await })
-            .map_err(|err| {
-                from_clickhouse_error(
-                    "[Clickhouse] Failed to create native clickhouse client",
-                    err,
-                )
-            })?;
+        let rt = self.runtime()?;
 
-        Ok(ClickhouseConnection::new(
-            Arc::new(rt),
-            arrow_conn,
-            native_conn,
-        ))
+        match self.pool(&rt)? {
+            Some(pool) => {
+                let session = pool.acquire(&rt, self.connect_timeout(), || self.connect(&rt))?;
+                Ok(ClickhouseConnection::from_session(rt, session)
+                    .with_pool(pool)
+                    .with_query_timeout(self.query_timeout()))
+            }
+            None => {
+                let session = self.connect(&rt)?;
+                Ok(ClickhouseConnection::from_session(rt, session)
+                    .with_query_timeout(self.query_timeout()))
+            }
+        }
     }
 
     fn new_connection_with_opts(
@@ -182,3 +632,486 @@ impl Database for ClickhouseDatabase {
         Ok(connection)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use adbc_core::{Connection, Statement};
+
+    use super::*;
+    use crate::{
+        consts::CONNECTION_OPTION_HOST,
+        stand_in::{self, Reply},
+    };
+
+    fn set(database: &mut ClickhouseDatabase, key: &str, value: &str) -> Result<()> {
+        database.set_option(key.into(), OptionValue::String(value.to_string()))
+    }
+
+    #[test]
+    fn rejects_session_options_once_the_pool_exists() {
+        let mut database = ClickhouseDatabase::default();
+        set(&mut database, "uri", "clickhouse://localhost:9000").unwrap();
+        *database.pool.lock().unwrap() =
+            Some(Arc::new(ConnectionPool::new(PoolOptions::default())));
+
+        for key in [
+            "uri",
+            "username",
+            "password",
+            DATABASE_OPTION_SCHEMA,
+            DATABASE_OPTION_COMPRESSION,
+            DATABASE_OPTION_TLS_ENABLED,
+            DATABASE_OPTION_TLS_SERVER_NAME,
+            DATABASE_OPTION_TLS_CA_FILE,
+            DATABASE_OPTION_POOL_MAX_SIZE,
+            "clickhouse.setting.max_threads",
+        ] {
+            let err = set(&mut database, key, "1").unwrap_err();
+            assert_eq!(err.status, Status::InvalidState, "{key}");
+        }
+
+        set(&mut database, DATABASE_OPTION_CONNECT_TIMEOUT_MS, "1000").unwrap();
+        set(&mut database, DATABASE_OPTION_LOAD_BALANCING, "in_order").unwrap();
+        assert_eq!(
+            database.get_option_string(OptionDatabase::Uri).unwrap(),
+            "clickhouse://localhost:9000"
+        );
+    }
+
+    #[test]
+    fn prefers_explicit_timeouts_over_the_uri() {
+        let mut database = ClickhouseDatabase::default();
+        set(
+            &mut database,
+            "uri",
+            "clickhouse://localhost?connect_timeout_ms=1500&query_timeout_ms=2000",
+        )
+        .unwrap();
+        assert_eq!(
+            database.connect_timeout(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(database.query_timeout(), Some(Duration::from_secs(2)));
+        assert!(database.parsed_uri.as_ref().unwrap().settings.is_empty());
+
+        set(&mut database, DATABASE_OPTION_CONNECT_TIMEOUT_MS, "0").unwrap();
+        set(&mut database, OPTION_QUERY_TIMEOUT_MS, "500").unwrap();
+        set(
+            &mut database,
+            "uri",
+            "clickhouse://localhost?connect_timeout_ms=3000",
+        )
+        .unwrap();
+        assert_eq!(database.connect_timeout(), None);
+        assert_eq!(database.query_timeout(), Some(Duration::from_millis(500)));
+        assert_eq!(
+            database
+                .get_option_string(OPTION_QUERY_TIMEOUT_MS.into())
+                .unwrap(),
+            "500"
+        );
+    }
+
+    #[test]
+    fn reads_numeric_options() {
+        let mut database = ClickhouseDatabase::default();
+        set(&mut database, "clickhouse.setting.max_threads", "4").unwrap();
+        set(&mut database, "clickhouse.setting.load_balancing", "random").unwrap();
+
+        let key = OptionDatabase::from("clickhouse.setting.max_threads");
+        assert_eq!(database.get_option_int(key.clone()).unwrap(), 4);
+        assert_eq!(database.get_option_double(key).unwrap(), 4.0);
+
+        let key = OptionDatabase::from("clickhouse.setting.load_balancing");
+        let err = database.get_option_int(key.clone()).unwrap_err();
+        assert_eq!(err.status, Status::InvalidArguments);
+        let err = database.get_option_double(key).unwrap_err();
+        assert_eq!(err.status, Status::InvalidArguments);
+
+        let key = OptionDatabase::from("clickhouse.setting.max_memory_usage");
+        let err = database.get_option_int(key).unwrap_err();
+        assert_eq!(err.status, Status::NotFound);
+        let err = database
+            .get_option_int(OptionDatabase::Username)
+            .unwrap_err();
+        assert_eq!(err.status, Status::NotFound);
+    }
+
+    const TEST_CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/localhost.crt");
+    const TEST_KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/localhost.key");
+
+    /// A TLS stand-in with a self-signed certificate for `localhost`, reporting the server name
+    /// the first handshake asked for and how it ended. With `client_auth` it also requires a
+    /// client certificate signed by that certificate.
+    fn tls_stand_in(
+        rt: &tokio::runtime::Runtime,
+        client_auth: bool,
+    ) -> (u16, std::sync::mpsc::Receiver<(Option<String>, String)>) {
+        use tokio_rustls::{
+            LazyConfigAcceptor,
+            rustls::{
+                RootCertStore, ServerConfig,
+                pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+                server::{Acceptor, WebPkiClientVerifier},
+            },
+        };
+
+        let certs = CertificateDer::pem_file_iter(TEST_CERT)
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        let key = PrivateKeyDer::from_pem_file(TEST_KEY).unwrap();
+        let builder = ServerConfig::builder();
+        let builder = if client_auth {
+            let mut roots = RootCertStore::empty();
+            roots.add_parsable_certificates(certs.clone());
+            builder.with_client_cert_verifier(
+                WebPkiClientVerifier::builder(Arc::new(roots))
+                    .build()
+                    .unwrap(),
+            )
+        } else {
+            builder.with_no_client_auth()
+        };
+        let config = Arc::new(builder.with_single_cert(certs, key).unwrap());
+
+        let listener = rt
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = std::sync::mpsc::channel();
+        rt.spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let start = LazyConfigAcceptor::new(Acceptor::default(), stream)
+                .await
+                .unwrap();
+            let server_name = start.client_hello().server_name().map(str::to_string);
+            let outcome = match start.into_stream(config).await {
+                Ok(_) => "accepted".to_string(),
+                Err(err) => err.to_string(),
+            };
+            let _ = tx.send((server_name, outcome));
+        });
+
+        (port, rx)
+    }
+
+    /// Connects to the stand-in over TLS, the connection itself fails once the handshake is done
+    /// as the stand-in does not speak the native protocol.
+    fn connect_over_tls(port: u16, options: &[(&str, &str)]) -> Error {
+        let mut database = ClickhouseDatabase::default();
+        set(
+            &mut database,
+            "uri",
+            &format!("clickhouses://127.0.0.1:{port}"),
+        )
+        .unwrap();
+        set(&mut database, DATABASE_OPTION_CONNECT_TIMEOUT_MS, "5000").unwrap();
+        for (key, value) in options {
+            set(&mut database, key, value).unwrap();
+        }
+
+        let Err(err) = database.new_connection() else {
+            panic!("connected to the TLS stand-in");
+        };
+        err
+    }
+
+    #[test]
+    fn refuses_an_untrusted_server_certificate() {
+        let stand_in = tokio::runtime::Runtime::new().unwrap();
+        let (port, handshake) = tls_stand_in(&stand_in, false);
+
+        let err = connect_over_tls(port, &[(DATABASE_OPTION_TLS_SERVER_NAME, "localhost")]);
+        assert!(err.message.contains("UnknownIssuer"), "{err:?}");
+
+        let (server_name, outcome) = handshake.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(server_name.as_deref(), Some("localhost"));
+        assert!(outcome.contains("UnknownCA"), "{outcome}");
+    }
+
+    #[test]
+    fn trusts_the_certificates_of_the_ca_file() {
+        let stand_in = tokio::runtime::Runtime::new().unwrap();
+        let (port, handshake) = tls_stand_in(&stand_in, false);
+
+        connect_over_tls(
+            port,
+            &[
+                (DATABASE_OPTION_TLS_SERVER_NAME, "localhost"),
+                (DATABASE_OPTION_TLS_CA_FILE, TEST_CERT),
+            ],
+        );
+
+        let (server_name, outcome) = handshake.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(server_name.as_deref(), Some("localhost"));
+        assert_eq!(outcome, "accepted");
+    }
+
+    #[test]
+    fn accepts_any_server_certificate_when_verification_is_skipped() {
+        let stand_in = tokio::runtime::Runtime::new().unwrap();
+        let (port, handshake) = tls_stand_in(&stand_in, false);
+
+        connect_over_tls(port, &[(DATABASE_OPTION_TLS_INSECURE_SKIP_VERIFY, "true")]);
+
+        let (_, outcome) = handshake.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(outcome, "accepted");
+    }
+
+    #[test]
+    fn presents_the_client_certificate() {
+        let stand_in = tokio::runtime::Runtime::new().unwrap();
+        let (port, handshake) = tls_stand_in(&stand_in, true);
+
+        connect_over_tls(
+            port,
+            &[
+                (DATABASE_OPTION_TLS_SERVER_NAME, "localhost"),
+                (DATABASE_OPTION_TLS_CA_FILE, TEST_CERT),
+                (DATABASE_OPTION_TLS_CLIENT_CERT, TEST_CERT),
+                (DATABASE_OPTION_TLS_CLIENT_KEY, TEST_KEY),
+            ],
+        );
+
+        let (_, outcome) = handshake.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(outcome, "accepted");
+    }
+
+    #[test]
+    fn connects_over_tls_when_tls_material_is_set() {
+        let stand_in = tokio::runtime::Runtime::new().unwrap();
+        let (port, handshake) = tls_stand_in(&stand_in, false);
+
+        // A bare host list leaves TLS unset, the CA file turns it on.
+        connect_over_tls(
+            port,
+            &[
+                ("uri", &format!("127.0.0.1:{port}")),
+                (DATABASE_OPTION_TLS_SERVER_NAME, "localhost"),
+                (DATABASE_OPTION_TLS_CA_FILE, TEST_CERT),
+            ],
+        );
+
+        let (_, outcome) = handshake.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(outcome, "accepted");
+    }
+
+    #[test]
+    fn rejects_tls_material_with_tls_turned_off() {
+        let err = connect_over_tls(
+            9000,
+            &[
+                (DATABASE_OPTION_TLS_ENABLED, "false"),
+                (DATABASE_OPTION_TLS_INSECURE_SKIP_VERIFY, "true"),
+            ],
+        );
+        assert_eq!(err.status, Status::InvalidArguments, "{err:?}");
+        assert!(err.message.contains(DATABASE_OPTION_TLS_ENABLED), "{err:?}");
+
+        let err = connect_over_tls(
+            9000,
+            &[
+                ("uri", "clickhouse://127.0.0.1:9000"),
+                (DATABASE_OPTION_TLS_CA_FILE, TEST_CERT),
+            ],
+        );
+        assert_eq!(err.status, Status::InvalidArguments, "{err:?}");
+    }
+
+    #[test]
+    fn rejects_a_client_certificate_without_its_key() {
+        // The TLS configuration fails before any host is connected to.
+        let err = connect_over_tls(9000, &[(DATABASE_OPTION_TLS_CLIENT_CERT, TEST_CERT)]);
+        assert_eq!(err.status, Status::InvalidArguments, "{err:?}");
+        assert!(
+            err.message.contains(DATABASE_OPTION_TLS_CLIENT_KEY),
+            "{err:?}"
+        );
+    }
+
+    /// A local port nothing listens on.
+    fn refusing_endpoint() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    /// A listener whose accept queue is full, so further connection attempts get no answer like a
+    /// host dropping packets. Keep the returned streams and listener alive while it is used.
+    fn unresponsive_endpoint() -> (String, impl Sized) {
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let listener = rt.block_on(async { socket.listen(0) }).unwrap();
+        let backlog = (0..4)
+            .filter_map(|_| {
+                std::net::TcpStream::connect_timeout(&addr, Duration::from_millis(100)).ok()
+            })
+            .collect::<Vec<_>>();
+        (addr.to_string(), (rt, listener, backlog))
+    }
+
+    fn connect(uri: &str) -> (Error, Duration) {
+        let mut database = ClickhouseDatabase::default();
+        set(&mut database, "uri", uri).unwrap();
+        set(&mut database, DATABASE_OPTION_CONNECT_TIMEOUT_MS, "300").unwrap();
+
+        let started = std::time::Instant::now();
+        let Err(err) = database.new_connection() else {
+            panic!("connected to {uri}");
+        };
+        (err, started.elapsed())
+    }
+
+    #[test]
+    fn reports_a_refused_connection() {
+        let endpoint = refusing_endpoint();
+        let (err, _) = connect(&format!("clickhouse://{endpoint}"));
+        assert_eq!(err.status, Status::IO, "{err:?}");
+        assert_eq!(
+            err.details.unwrap(),
+            [("clickhouse.endpoint".to_string(), endpoint.into_bytes())]
+        );
+    }
+
+    #[test]
+    fn times_out_connecting_to_a_silent_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = listener.local_addr().unwrap();
+        let (err, elapsed) = connect(&format!("clickhouse://{endpoint}"));
+        assert_eq!(err.status, Status::Timeout, "{err:?}");
+        assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+    }
+
+    #[test]
+    fn times_out_connecting_to_an_unresponsive_host() {
+        let (endpoint, _listener) = unresponsive_endpoint();
+        let (err, elapsed) = connect(&format!("clickhouse://{endpoint}"));
+        assert_eq!(err.status, Status::Timeout, "{err:?}");
+        assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+    }
+
+    #[test]
+    fn fails_over_to_the_next_host() {
+        let refusing = refusing_endpoint();
+        let (unresponsive, _listener) = unresponsive_endpoint();
+        let (err, elapsed) = connect(&format!("clickhouse://{refusing},{unresponsive}"));
+
+        assert_eq!(err.status, Status::Timeout, "{err:?}");
+        assert!(
+            err.message
+                .starts_with("[Clickhouse] Failed to connect to any host")
+        );
+        assert!(err.message.contains(&refusing), "{}", err.message);
+        assert!(err.message.contains(&unresponsive), "{}", err.message);
+        assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+    }
+
+    #[test]
+    fn connects_to_the_first_host_that_answers() {
+        let refusing = refusing_endpoint();
+        let (unresponsive, _listener) = unresponsive_endpoint();
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let (stand_in, queries) = stand_in::start(&server, |_| Reply::Exception(1000));
+
+        let mut database = ClickhouseDatabase::default();
+        set(
+            &mut database,
+            "uri",
+            &format!("clickhouse://{refusing},{unresponsive},{stand_in}"),
+        )
+        .unwrap();
+        set(&mut database, DATABASE_OPTION_CONNECT_TIMEOUT_MS, "300").unwrap();
+        let connection = database.new_connection().unwrap();
+
+        // Connecting reads the server version.
+        let query = queries.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(query.contains("SELECT version()"), "{query}");
+        assert_eq!(
+            connection
+                .get_option_string(CONNECTION_OPTION_HOST.into())
+                .unwrap(),
+            stand_in
+        );
+    }
+
+    /// A pooled database on the stand-in, with the channel of the queries it answered.
+    fn pooled_stand_in(
+        server: &tokio::runtime::Runtime,
+    ) -> (ClickhouseDatabase, std::sync::mpsc::Receiver<String>) {
+        let (endpoint, queries) = stand_in::start(server, |query| {
+            if query.starts_with("SET") {
+                Reply::EndOfStream
+            } else {
+                Reply::Exception(1000)
+            }
+        });
+
+        let mut database = ClickhouseDatabase::default();
+        set(&mut database, "uri", &endpoint).unwrap();
+        set(&mut database, DATABASE_OPTION_POOL_ENABLED, "true").unwrap();
+        // The stand-in does not answer pings.
+        set(&mut database, DATABASE_OPTION_POOL_HEALTH_CHECK, "false").unwrap();
+        (database, queries)
+    }
+
+    /// How many sessions were opened, each of them reading the server version.
+    fn sessions_opened(queries: &std::sync::mpsc::Receiver<String>) -> usize {
+        queries
+            .try_iter()
+            .filter(|query| query.contains("SELECT version()"))
+            .count()
+    }
+
+    #[test]
+    fn keeps_a_session_with_live_statements_out_of_the_pool() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let (database, queries) = pooled_stand_in(&server);
+
+        let mut connection = database.new_connection().unwrap();
+        let statement = connection.new_statement().unwrap();
+        drop(connection);
+        let connection = database.new_connection().unwrap();
+        assert_eq!(sessions_opened(&queries), 2);
+
+        drop(statement);
+        drop(connection);
+        let _connection = database.new_connection().unwrap();
+        assert_eq!(sessions_opened(&queries), 0);
+    }
+
+    #[test]
+    fn discards_a_session_changed_by_a_statement() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let (database, queries) = pooled_stand_in(&server);
+
+        let mut connection = database.new_connection().unwrap();
+        let mut statement = connection.new_statement().unwrap();
+        statement.set_sql_query("SET max_threads = 1").unwrap();
+        statement.execute_update().unwrap();
+        drop(statement);
+        drop(connection);
+        assert_eq!(sessions_opened(&queries), 1);
+
+        let _connection = database.new_connection().unwrap();
+        assert_eq!(sessions_opened(&queries), 1);
+    }
+
+    #[test]
+    fn drops_a_pool_that_cannot_be_filled() {
+        let mut database = ClickhouseDatabase::default();
+        set(
+            &mut database,
+            "uri",
+            &format!("clickhouse://{}", refusing_endpoint()),
+        )
+        .unwrap();
+        set(&mut database, DATABASE_OPTION_POOL_ENABLED, "true").unwrap();
+        set(&mut database, DATABASE_OPTION_POOL_MIN_SIZE, "1").unwrap();
+
+        assert!(database.new_connection().is_err());
+        assert!(database.pool.lock().unwrap().is_none());
+        set(&mut database, DATABASE_OPTION_POOL_MIN_SIZE, "0").unwrap();
+    }
+}
@@ -7,7 +7,10 @@ pub mod statement;
 
 pub(crate) mod utils;
 
+mod pool;
 mod schemas;
+#[cfg(test)]
+mod stand_in;
 
 pub use connection::*;
 pub use consts::*;
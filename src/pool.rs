@@ -0,0 +1,347 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use adbc_core::error::{Error, Result, Status};
+
+use crate::{connection::ClientSession, utils::Runtime};
+
+#[derive(Debug, Clone)]
+pub(crate) struct PoolOptions {
+    pub enabled: bool,
+    pub min_size: usize,
+    pub max_size: usize,
+    /// Idle sessions above `min_size` are closed once unused for this long. `None` keeps them.
+    pub idle_timeout: Option<Duration>,
+    pub health_check: bool,
+    pub acquire_timeout: Duration,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_size: 0,
+            max_size: 10,
+            idle_timeout: Some(Duration::from_secs(300)),
+            health_check: true,
+            acquire_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// What the pool needs from the sessions it keeps.
+pub(crate) trait PooledSession: Send + 'static {
+    fn is_healthy(&self) -> impl Future<Output = bool> + Send;
+}
+
+impl PooledSession for ClientSession {
+    fn is_healthy(&self) -> impl Future<Output = bool> + Send {
+        ClientSession::is_healthy(self)
+    }
+}
+
+struct IdleSession<S> {
+    session: S,
+    since: Instant,
+}
+
+struct PoolState<S> {
+    idle: VecDeque<IdleSession<S>>,
+    /// Sessions currently alive, whether idle or checked out by a connection.
+    open: usize,
+}
+
+/// Keeps established [`ClientSession`]s around so short lived ADBC connections can skip the
+/// handshake. Sessions are checked out by [`ConnectionPool::acquire`] and come back when the
+/// owning `ClickhouseConnection` is dropped.
+pub(crate) struct ConnectionPool<S = ClientSession> {
+    options: PoolOptions,
+    state: Mutex<PoolState<S>>,
+    available: Condvar,
+}
+
+impl<S: PooledSession> ConnectionPool<S> {
+    pub fn new(mut options: PoolOptions) -> Self {
+        options.min_size = options.min_size.min(options.max_size);
+
+        Self {
+            options,
+            state: Mutex::new(PoolState {
+                idle: VecDeque::new(),
+                open: 0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Opens `min_size` sessions up front.
+    pub fn fill(&self, connect: impl Fn() -> Result<S>) -> Result<()> {
+        for _ in 0..self.options.min_size {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.open >= self.options.min_size {
+                    break;
+                }
+                state.open += 1;
+            }
+
+            let session = connect().inspect_err(|_| self.discard())?;
+            self.release(session);
+        }
+
+        Ok(())
+    }
+
+    /// Hands out an idle session, opening a new one while below `max_size`, or waits up to
+    /// `acquire_timeout` for another connection to give one back. A health check taking longer
+    /// than `check_timeout` counts as failed.
+    pub fn acquire(
+        &self,
+        rt: &Runtime,
+        check_timeout: Option<Duration>,
+        connect: impl Fn() -> Result<S>,
+    ) -> Result<S> {
+        let deadline = Instant::now() + self.options.acquire_timeout;
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            self.evict_expired(&mut state);
+
+            if let Some(idle) = state.idle.pop_back() {
+                if !self.options.health_check {
+                    return Ok(idle.session);
+                }
+
+                drop(state);
+                let is_healthy = idle.session.is_healthy();
+                let is_healthy = rt.block_on(async {
+                    match check_timeout {
+                        Some(check_timeout) => tokio::time::timeout(check_timeout, is_healthy)
+                            .await
+                            .unwrap_or(false),
+                        None => is_healthy.await,
+                    }
+                });
+                if is_healthy {
+                    return Ok(idle.session);
+                }
+
+                drop(idle);
+                state = self.state.lock().unwrap();
+                state.open -= 1;
+                continue;
+            }
+
+            if state.open < self.options.max_size {
+                state.open += 1;
+                drop(state);
+                return connect().inspect_err(|_| self.discard());
+            }
+
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                return Err(Error::with_message_and_status(
+                    format!(
+                        "[Clickhouse] Timed out waiting for a pooled connection, all {} are in use",
+                        self.options.max_size
+                    ),
+                    Status::Timeout,
+                ));
+            }
+
+            state = self.available.wait_timeout(state, timeout).unwrap().0;
+        }
+    }
+
+    /// Returns a healthy session to the pool, closing those idle for too long.
+    pub fn release(&self, session: S) {
+        let mut state = self.state.lock().unwrap();
+        state.idle.push_back(IdleSession {
+            session,
+            since: Instant::now(),
+        });
+        self.evict_expired(&mut state);
+        self.available.notify_one();
+    }
+
+    /// Closes expired idle sessions in the background as well, so they do not stay open once
+    /// nothing acquires or releases sessions anymore. Stops when the pool is dropped.
+    pub fn start_eviction(self: &Arc<Self>, rt: &Runtime) {
+        let Some(idle_timeout) = self.options.idle_timeout else {
+            return;
+        };
+
+        let pool = Arc::downgrade(self);
+        rt.spawn(async move {
+            let mut interval = tokio::time::interval(idle_timeout / 2);
+            loop {
+                interval.tick().await;
+                let Some(pool) = Weak::upgrade(&pool) else {
+                    return;
+                };
+                pool.evict_expired(&mut pool.state.lock().unwrap());
+            }
+        });
+    }
+
+    /// Forgets a checked out session that was closed instead of returned.
+    pub fn discard(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.open -= 1;
+        self.available.notify_one();
+    }
+
+    fn evict_expired(&self, state: &mut PoolState<S>) {
+        let Some(idle_timeout) = self.options.idle_timeout else {
+            return;
+        };
+
+        // The least recently used sessions sit at the front of the queue.
+        while state.open > self.options.min_size
+            && state
+                .idle
+                .front()
+                .is_some_and(|idle| idle.since.elapsed() >= idle_timeout)
+        {
+            state.idle.pop_front();
+            state.open -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestSession {
+        id: usize,
+        healthy: bool,
+    }
+
+    impl PooledSession for TestSession {
+        async fn is_healthy(&self) -> bool {
+            self.healthy
+        }
+    }
+
+    fn options(min_size: usize, max_size: usize, idle_timeout: Option<Duration>) -> PoolOptions {
+        PoolOptions {
+            enabled: true,
+            min_size,
+            max_size,
+            idle_timeout,
+            health_check: true,
+            acquire_timeout: Duration::from_millis(50),
+        }
+    }
+
+    /// Opens sessions with increasing ids.
+    fn connector(healthy: bool) -> impl Fn() -> Result<TestSession> {
+        let next_id = AtomicUsize::new(0);
+        move || {
+            Ok(TestSession {
+                id: next_id.fetch_add(1, Ordering::Relaxed),
+                healthy,
+            })
+        }
+    }
+
+    fn open(pool: &ConnectionPool<TestSession>) -> usize {
+        pool.state.lock().unwrap().open
+    }
+
+    #[test]
+    fn reuses_released_sessions() {
+        let rt = Runtime::new(None).unwrap();
+        let pool = ConnectionPool::new(options(1, 2, None));
+        let connect = connector(true);
+        pool.fill(&connect).unwrap();
+        assert_eq!(open(&pool), 1);
+
+        let first = pool.acquire(&rt, None, &connect).unwrap();
+        let second = pool.acquire(&rt, None, &connect).unwrap();
+        assert_eq!((first.id, second.id), (0, 1));
+
+        let err = pool.acquire(&rt, None, &connect).unwrap_err();
+        assert_eq!(err.status, Status::Timeout);
+
+        pool.release(second);
+        assert_eq!(pool.acquire(&rt, None, &connect).unwrap().id, 1);
+        pool.discard();
+        assert_eq!(open(&pool), 1);
+    }
+
+    #[test]
+    fn replaces_unhealthy_sessions() {
+        let rt = Runtime::new(None).unwrap();
+        let pool = ConnectionPool::new(options(0, 1, None));
+        pool.release(TestSession {
+            id: 10,
+            healthy: false,
+        });
+        pool.state.lock().unwrap().open = 1;
+
+        let session = pool.acquire(&rt, None, connector(true)).unwrap();
+        assert_eq!(session.id, 0);
+        assert_eq!(open(&pool), 1);
+    }
+
+    #[test]
+    fn evicts_idle_sessions_on_release() {
+        let rt = Runtime::new(None).unwrap();
+        let pool = ConnectionPool::new(options(1, 3, Some(Duration::from_millis(20))));
+        let connect = connector(true);
+        let sessions = (0..3)
+            .map(|_| pool.acquire(&rt, None, &connect).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut sessions = sessions.into_iter();
+        pool.release(sessions.next().unwrap());
+        pool.release(sessions.next().unwrap());
+        std::thread::sleep(Duration::from_millis(30));
+        pool.release(sessions.next().unwrap());
+
+        // Expired sessions are closed down to `min_size`, the fresh one stays.
+        assert_eq!(open(&pool), 1);
+        assert_eq!(pool.state.lock().unwrap().idle[0].session.id, 2);
+    }
+
+    #[test]
+    fn evicts_idle_sessions_in_the_background() {
+        let rt = Runtime::new(None).unwrap();
+        let pool = Arc::new(ConnectionPool::new(options(
+            0,
+            2,
+            Some(Duration::from_millis(20)),
+        )));
+        let connect = connector(true);
+        let session = pool.acquire(&rt, None, &connect).unwrap();
+        pool.release(session);
+        pool.start_eviction(&rt);
+
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(open(&pool), 0);
+        assert!(pool.state.lock().unwrap().idle.is_empty());
+    }
+
+    #[test]
+    fn gives_up_filling_when_a_connection_fails() {
+        let pool = ConnectionPool::<TestSession>::new(options(2, 2, None));
+        let err = pool
+            .fill(|| {
+                Err(Error::with_message_and_status(
+                    "[Clickhouse] refused",
+                    Status::IO,
+                ))
+            })
+            .unwrap_err();
+        assert_eq!(err.status, Status::IO);
+        assert_eq!(open(&pool), 0);
+    }
+}
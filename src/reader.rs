@@ -1,11 +1,12 @@
 use std::{pin::Pin, sync::Arc};
 
+use adbc_core::error::Result;
 use arrow_array::{RecordBatch, RecordBatchReader};
-use arrow_schema::{ArrowError, Schema};
+use arrow_schema::{ArrowError, Schema, SchemaRef};
 use clickhouse_arrow::ClickHouseResponse;
 use futures::{StreamExt, stream::Peekable};
 
-use crate::utils::Runtime;
+use crate::utils::{Interrupt, Runtime, from_clickhouse_error};
 
 #[derive(Debug)]
 pub struct SingleBatchReader {
@@ -40,24 +41,45 @@ impl RecordBatchReader for SingleBatchReader {
 pub struct ClickhouseReader {
     rt: Arc<Runtime>,
     stream: Pin<Box<Peekable<ClickHouseResponse<RecordBatch>>>>,
-    schema: Option<Arc<arrow_schema::Schema>>,
+    schema: SchemaRef,
+    interrupt: Interrupt,
 }
 
 impl ClickhouseReader {
-    pub fn new(rt: Arc<Runtime>, stream: ClickHouseResponse<RecordBatch>) -> Self {
+    /// Creates a reader which gives up once `interrupt` fires, both while waiting for the first
+    /// batch here and for the following ones in [`Iterator::next`].
+    ///
+    /// The schema is taken from the first batch. The server sends none for an empty result, whose
+    /// schema comes from `empty_schema` instead, given `interrupt` to bound any query it runs. An
+    /// error in the first batch or from `empty_schema` is returned here.
+    pub(crate) fn try_new(
+        rt: Arc<Runtime>,
+        stream: ClickHouseResponse<RecordBatch>,
+        interrupt: Interrupt,
+        empty_schema: impl FnOnce(&Interrupt) -> Result<Schema>,
+    ) -> Result<Self> {
         let mut peekable = Box::pin(stream.peekable());
-        let schema = rt
-            .block_on(peekable.as_mut().peek())
-            .map(|res| res.as_ref().map(|res| res.schema()))
-            .transpose()
-            .ok()
-            .flatten();
+        let schema = rt.block_on(interrupt.run(async {
+            match peekable.as_mut().peek().await {
+                Some(Ok(batch)) => Ok(Some(batch.schema())),
+                Some(Err(_)) => match peekable.as_mut().next().await {
+                    Some(Err(err)) => Err(from_clickhouse_error("Failed to execute query", err)),
+                    _ => Ok(None),
+                },
+                None => Ok(None),
+            }
+        }))?;
+        let schema = match schema {
+            Some(schema) => schema,
+            None => Arc::new(empty_schema(&interrupt)?),
+        };
 
-        Self {
+        Ok(Self {
             rt,
             stream: peekable,
             schema,
-        }
+            interrupt,
+        })
     }
 }
 
@@ -65,13 +87,21 @@ impl Iterator for ClickhouseReader {
     type Item = std::result::Result<RecordBatch, ArrowError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next = self.rt.block_on(self.stream.next())?;
-        Some(next.map_err(|err| ArrowError::ExternalError(Box::new(err))))
+        self.rt
+            .block_on(self.interrupt.run(async {
+                self.stream
+                    .next()
+                    .await
+                    .transpose()
+                    .map_err(|err| from_clickhouse_error("Failed to read query result", err))
+            }))
+            .map_err(|err| ArrowError::ExternalError(Box::new(err)))
+            .transpose()
     }
 }
 
 impl RecordBatchReader for ClickhouseReader {
-    fn schema(&self) -> arrow_schema::SchemaRef {
-        self.schema.clone().expect("failed to fetch schema")
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
     }
 }
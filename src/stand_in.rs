@@ -0,0 +1,139 @@
+//! A stand-in for a ClickHouse server speaking just enough of the native protocol for tests
+//! which need a connection but no query results.
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Revision the stand-in greets with, the oldest one negotiating whether packets are chunked.
+const REVISION: u64 = 54470;
+
+/// How the stand-in answers a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reply {
+    /// Ends the query without any data.
+    EndOfStream,
+    /// Fails the query with an exception of `code`.
+    Exception(i32),
+    /// Sends nothing until the client cancels the query, then fails it with `QUERY_WAS_CANCELLED`.
+    AwaitCancel,
+}
+
+/// Exception code of a query the client cancelled.
+pub(crate) const QUERY_WAS_CANCELLED: i32 = 394;
+
+/// Message of every exception the stand-in sends.
+pub(crate) const EXCEPTION_MESSAGE: &str = "Stand-in does not run queries";
+
+/// Listens on a local port, answering each query with the reply `reply` gives for its text,
+/// and reports every query packet it answered on the returned channel, as lossy UTF-8 which
+/// includes the settings sent along with the query.
+pub(crate) fn start(
+    rt: &tokio::runtime::Runtime,
+    reply: fn(&str) -> Reply,
+) -> (String, std::sync::mpsc::Receiver<String>) {
+    let listener = rt.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+    let endpoint = listener.local_addr().unwrap().to_string();
+    let (tx, rx) = std::sync::mpsc::channel();
+    rt.spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream, reply, tx.clone()));
+        }
+    });
+
+    (endpoint, rx)
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    reply: fn(&str) -> Reply,
+    queries: std::sync::mpsc::Sender<String>,
+) -> std::io::Result<()> {
+    // Greets right away, the client hello is read along with the first query and ignored.
+    let mut hello = vec![0]; // Hello
+    write_string(&mut hello, "ClickHouse");
+    write_var_uint(&mut hello, 24);
+    write_var_uint(&mut hello, 8);
+    write_var_uint(&mut hello, REVISION);
+    write_string(&mut hello, "UTC");
+    write_string(&mut hello, "stand-in");
+    write_var_uint(&mut hello, 1);
+    write_string(&mut hello, "notchunked"); // send
+    write_string(&mut hello, "notchunked"); // receive
+    write_var_uint(&mut hello, 0); // password complexity rules
+    hello.extend_from_slice(&0u64.to_le_bytes()); // interserver secret nonce
+    stream.write_all(&hello).await?;
+
+    let mut buf = vec![0; 64 * 1024];
+    let mut received = vec![];
+    loop {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        received.extend_from_slice(&buf[..read]);
+
+        // A query packet carries its text, the data block ending it may follow separately.
+        let text = String::from_utf8_lossy(&received).into_owned();
+        let Some(query) = ["SELECT", "INSERT", "CREATE", "DROP", "KILL", "SET", "USE"]
+            .iter()
+            .filter_map(|keyword| text.find(keyword))
+            .min()
+            .map(|start| &text[start..])
+        else {
+            continue;
+        };
+        let reply = reply(query);
+        let _ = queries.send(text.clone());
+        received.clear();
+
+        let code = match reply {
+            Reply::EndOfStream => {
+                stream.write_all(&[5]).await?; // EndOfStream
+                continue;
+            }
+            Reply::Exception(code) => code,
+            Reply::AwaitCancel => {
+                await_cancel(&mut stream, &mut buf).await?;
+                QUERY_WAS_CANCELLED
+            }
+        };
+        stream.write_all(&exception(code)).await?;
+    }
+}
+
+/// Waits for the Cancel packet, the byte 3 on its own. It ends what the client sent so far as the
+/// empty data block following a query never ends with it.
+async fn await_cancel(stream: &mut TcpStream, buf: &mut [u8]) -> std::io::Result<()> {
+    loop {
+        match stream.read(buf).await? {
+            0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            read if buf[read - 1] == 3 => return Ok(()), // Cancel
+            _ => {}
+        }
+    }
+}
+
+fn exception(code: i32) -> Vec<u8> {
+    let mut packet = vec![2]; // Exception
+    packet.extend_from_slice(&code.to_le_bytes());
+    write_string(&mut packet, "DB::Exception");
+    write_string(&mut packet, EXCEPTION_MESSAGE);
+    write_string(&mut packet, "");
+    packet.push(0); // has_nested
+    packet
+}
+
+fn write_var_uint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_string(buf: &mut Vec<u8>, value: &str) {
+    write_var_uint(buf, value.len() as u64);
+    buf.extend_from_slice(value.as_bytes());
+}
@@ -1,4 +1,10 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
 use adbc_core::{
     Optionable, Statement, constants,
@@ -6,23 +12,54 @@ use adbc_core::{
     options::{OptionStatement, OptionValue},
 };
 use arrow_array::RecordBatchReader;
+use arrow_schema::Schema;
+use clickhouse_arrow::{ArrowClient, Qid, QueryParams, Settings};
+use futures::StreamExt;
+use tokio::time::Instant;
 
 use crate::{
-    reader::ClickhouseReader,
-    utils::{Runtime, from_clickhouse_error},
+    consts::{OPTION_QUERY_TIMEOUT_MS, STATEMENT_OPTION_INGEST_PARALLELISM},
+    reader::{ClickhouseReader, SingleBatchReader},
+    utils::{
+        ArrowClientExt, BlockCoalescer, BlockSize, CancelState, ClientFactory, IngestMode,
+        Interrupt, ParallelInsert, PreparedQuery, QueryDetails, Runtime, SettingsMap, TableOptions,
+        changes_session, create_table_query, from_clickhouse_error, has_identifier_placeholders,
+        insert_streamed, is_insert, is_introspection, parameter_schema, parse_bool_option,
+        parse_number_option, parse_table_name, parse_timeout_option, quote_table, result_schema,
+        returns_rows, row_params, schema_mismatch_error, send_block, setting_name,
+        substitute_placeholders, timeout_millis, track_written_rows,
+    },
 };
 
 pub struct ClickhouseStatement {
     rt: Arc<Runtime>,
-    conn: clickhouse_arrow::ArrowClient,
+    conn: ArrowClient,
     sql_query: Option<String>,
     bound_record_batch: Option<arrow_array::RecordBatch>,
     bound_record_batch_reader: Option<Box<dyn RecordBatchReader + Send>>,
     ingest_target_table: Option<String>,
+    /// ClickHouse has no catalogs, a catalog names a database just like a db_schema.
+    ingest_target_catalog: Option<String>,
+    ingest_target_db_schema: Option<String>,
+    ingest_mode: IngestMode,
+    table_options: TableOptions,
+    block_size: BlockSize,
+    ingest_parallelism: usize,
+    settings: SettingsMap,
+    query_timeout: Option<Duration>,
+    cancel: Arc<CancelState>,
+    /// The `host:port` of the connection, reported in error details.
+    host: Option<String>,
+    /// Set when the statement changes the server session of its connection.
+    session_modified: Arc<AtomicBool>,
+    prepared: Option<PreparedQuery>,
+    /// Opens the connections of parallel ingest, with the connection level settings applied.
+    factory: Option<ClientFactory>,
 }
 
 impl ClickhouseStatement {
-    pub fn new(rt: Arc<Runtime>, conn: clickhouse_arrow::ArrowClient) -> Self {
+    pub fn new(rt: Arc<Runtime>, conn: ArrowClient) -> Self {
+        let cancel = Arc::new(CancelState::new(Some(conn.clone()), None));
         Self {
             rt,
             conn,
@@ -30,10 +67,420 @@ impl ClickhouseStatement {
             bound_record_batch: None,
             bound_record_batch_reader: None,
             ingest_target_table: None,
+            ingest_target_catalog: None,
+            ingest_target_db_schema: None,
+            ingest_mode: IngestMode::default(),
+            table_options: TableOptions::default(),
+            block_size: BlockSize::default(),
+            ingest_parallelism: 1,
+            settings: SettingsMap::new(),
+            query_timeout: None,
+            cancel,
+            host: None,
+            session_modified: Arc::new(AtomicBool::new(false)),
+            prepared: None,
+            factory: None,
+        }
+    }
+
+    pub(crate) fn with_factory(mut self, factory: Option<ClientFactory>) -> Self {
+        self.factory = factory;
+        self
+    }
+
+    pub(crate) fn with_query_timeout(mut self, query_timeout: Option<Duration>) -> Self {
+        self.query_timeout = query_timeout;
+        self
+    }
+
+    /// Lets the connection cancel this statement's queries and kill them on the server.
+    pub(crate) fn with_cancel_state(mut self, cancel: Arc<CancelState>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub(crate) fn with_host(mut self, host: Option<String>) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Lets the statement mark the connection's session as modified, so a pooled session with
+    /// temporary tables is not handed out again.
+    pub(crate) fn with_session_modified(mut self, session_modified: Arc<AtomicBool>) -> Self {
+        self.session_modified = session_modified;
+        self
+    }
+
+    fn start_query(&self, query: &str) -> Interrupt {
+        let deadline = self
+            .query_timeout
+            .map(|query_timeout| Instant::now() + query_timeout);
+        let details = QueryDetails::new(query, self.host.as_deref());
+        self.cancel.start(deadline, details)
+    }
+
+    /// The statement settings plus `max_execution_time` derived from the query timeout, unless it
+    /// was set explicitly, so the server gives up on the query too.
+    fn query_settings(&self) -> Settings {
+        let mut settings = self.settings.clone().into_iter().collect::<Settings>();
+        if let Some(query_timeout) = self.query_timeout
+            && !self.settings.contains_key("max_execution_time")
+        {
+            settings.add_setting(
+                "max_execution_time",
+                query_timeout.as_secs_f64().ceil().to_string(),
+            );
+        }
+        settings
+    }
+
+    /// The statement's connection, sending the statement settings with every query rather than
+    /// in a `SETTINGS` clause, which not every statement takes.
+    fn client(&self) -> ArrowClient {
+        self.conn.with_query_settings(self.query_settings())
+    }
+
+    /// Marks the connection's session as modified when `query` changes it, see
+    /// [`changes_session`], so a pooled session is not handed out again.
+    fn track_session_change(&self, query: &str) {
+        if changes_session(query) {
+            self.session_modified.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Fails if the statement was prepared and its query has been replaced since.
+    fn check_prepared(&self) -> Result<()> {
+        match &self.prepared {
+            Some(prepared) if Some(&prepared.query) != self.sql_query.as_ref() => {
+                Err(Error::with_message_and_status(
+                    "[Clickhouse] SQL query changed since the statement was prepared, prepare it again",
+                    Status::InvalidState,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Asks the server for the result schema of `query` with `params` without running it.
+    fn describe(&self, query: &str, params: Option<QueryParams>) -> Result<Schema> {
+        let interrupt = self.start_query(query);
+        self.describe_with(query, params, &interrupt)
+    }
+
+    fn describe_with(
+        &self,
+        query: &str,
+        params: Option<QueryParams>,
+        interrupt: &Interrupt,
+    ) -> Result<Schema> {
+        let columns = self.rt.block_on(interrupt.run(async {
+            self.client()
+                .describe_query(query, params, interrupt.qid())
+                .await
+                .map_err(|err| from_clickhouse_error("Failed to describe query", err))
+        }))?;
+        result_schema(columns)
+    }
+
+    /// The schema of the first batch `query` returns, for statements which cannot be described.
+    /// The server sends no batch for an empty result, which leaves the schema unknown.
+    fn first_batch_schema(&self, query: &str) -> Result<Schema> {
+        let interrupt = self.start_query(query);
+        let batch = self.rt.block_on(interrupt.run(async {
+            let mut response = self
+                .client()
+                .query(query, interrupt.qid())
+                .await
+                .map_err(|err| from_clickhouse_error("Failed to execute query", err))?;
+            response
+                .next()
+                .await
+                .transpose()
+                .map_err(|err| from_clickhouse_error("Failed to execute query", err))
+        }))?;
+
+        match batch {
+            Some(batch) => Ok(batch.schema().as_ref().clone()),
+            None => Err(Error::with_message_and_status(
+                "[Clickhouse] Cannot determine the schema of a statement which returns no rows",
+                Status::NotImplemented,
+            )),
+        }
+    }
+
+    /// The schema of an empty result of `query` run with `params`, for which the server sends no
+    /// batch. A `SELECT` is described with the same parameters as a follow-up of the query's
+    /// `interrupt`, statements which return no rows have an empty schema.
+    fn empty_result_schema(
+        &self,
+        query: &str,
+        params: Option<QueryParams>,
+        interrupt: &Interrupt,
+    ) -> Result<Schema> {
+        if let Some(schema) = self
+            .prepared
+            .as_ref()
+            .and_then(|prepared| prepared.schema.clone())
+        {
+            return Ok(schema);
+        }
+
+        if returns_rows(query) {
+            let interrupt = interrupt.follow_up(QueryDetails::new(query, self.host.as_deref()));
+            self.describe_with(query, params, &interrupt)
+        } else {
+            Ok(Schema::empty())
+        }
+    }
+
+    /// The schema of the rows the query returns, from the prepared statement or by describing the
+    /// query. `SHOW` and the like are run instead, statements which return no rows have an empty
+    /// schema.
+    fn query_schema(&self) -> Result<Schema> {
+        if let Some(schema) = self
+            .prepared
+            .as_ref()
+            .and_then(|prepared| prepared.schema.clone())
+        {
+            return Ok(schema);
+        }
+
+        match &self.sql_query {
+            Some(query)
+                if has_identifier_placeholders(query)
+                    && (returns_rows(query) || is_introspection(query)) =>
+            {
+                Err(Error::with_message_and_status(
+                    "[Clickhouse] The result schema depends on Identifier parameters, execute the query to get it",
+                    Status::NotImplemented,
+                ))
+            }
+            Some(query) if returns_rows(query) => {
+                self.describe(&substitute_placeholders(query), None)
+            }
+            Some(query) if is_introspection(query) => {
+                self.first_batch_schema(&substitute_placeholders(query))
+            }
+            Some(_) => Ok(Schema::empty()),
+            None => Err(Error::with_message_and_status(
+                "[Clickhouse] SQL query is empty",
+                Status::InvalidState,
+            )),
+        }
+    }
+
+    /// One set of query parameters per bound row, or `None` when nothing is bound. A bound batch
+    /// is kept for the next execution while a bound stream is consumed.
+    fn bound_params(&mut self) -> Result<Option<Vec<QueryParams>>> {
+        let batches = if let Some(batch) = &self.bound_record_batch {
+            vec![batch.clone()]
+        } else if let Some(reader) = self.bound_record_batch_reader.take() {
+            reader.collect::<std::result::Result<Vec<_>, _>>()?
+        } else {
+            return Ok(None);
+        };
+
+        batches
+            .iter()
+            .flat_map(|batch| (0..batch.num_rows()).map(move |row| row_params(batch, row)))
+            .collect::<Result<Vec<_>>>()
+            .map(Some)
+    }
+
+    /// The quoted ingest target table. With a catalog or db_schema set the target table is taken
+    /// literally, otherwise a `db.table` name is split, see [`parse_table_name`].
+    fn ingest_target(&self, target_table: &str) -> Result<String> {
+        let database = match (&self.ingest_target_catalog, &self.ingest_target_db_schema) {
+            (Some(catalog), Some(db_schema)) if catalog != db_schema => {
+                return Err(Error::with_message_and_status(
+                    format!(
+                        "[Clickhouse] Ingest target catalog {catalog} and db_schema {db_schema} name different databases"
+                    ),
+                    Status::InvalidArguments,
+                ));
+            }
+            (catalog, db_schema) => db_schema.as_ref().or(catalog.as_ref()),
+        };
+        let (database, table) = match database {
+            Some(database) => (Some(database.clone()), target_table.to_string()),
+            None => parse_table_name(target_table)?,
+        };
+
+        if database.is_some() && self.table_options.temporary {
+            return Err(Error::with_message_and_status(
+                "[Clickhouse] Temporary tables cannot be created in a database",
+                Status::InvalidArguments,
+            ));
         }
+
+        Ok(quote_table(database.as_deref(), &table))
+    }
+
+    /// The bound batch or stream, both consumed by ingest.
+    fn take_bound_data(&mut self) -> Option<Box<dyn RecordBatchReader + Send>> {
+        match self.bound_record_batch.take() {
+            Some(batch) => Some(Box::new(SingleBatchReader::new(batch))),
+            None => self.bound_record_batch_reader.take(),
+        }
+    }
+
+    /// Prepares the target table as the ingest mode requires, then inserts every batch of
+    /// `reader` into it.
+    fn ingest(
+        &self,
+        target_table: &str,
+        reader: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<usize> {
+        let parallel_factory = self.parallel_factory()?;
+        let schema = reader.schema();
+        self.create_target_table(target_table, &schema)?;
+
+        let query = format!("INSERT INTO {target_table} FORMAT Native");
+        let interrupt = self.start_query(&query);
+        let coalescer = BlockCoalescer::new(schema, self.block_size);
+        self.rt
+            .block_on(interrupt.run(async {
+                match parallel_factory {
+                    Some(factory) => {
+                        let insert = ParallelInsert::start(
+                            factory,
+                            &query,
+                            self.query_settings(),
+                            self.ingest_parallelism,
+                            &interrupt,
+                        )
+                        .await?;
+                        insert_parallel(insert, coalescer, reader).await
+                    }
+                    None => {
+                        self.insert_sequential(&query, interrupt.qid(), coalescer, reader)
+                            .await
+                    }
+                }
+            }))
+            .map_err(|err| {
+                if self.ingest_mode.appends() {
+                    schema_mismatch_error(err)
+                } else {
+                    err
+                }
+            })
+    }
+
+    /// Opens the ingest connections when ingesting in parallel, `None` to ingest through
+    /// the statement's connection.
+    fn parallel_factory(&self) -> Result<Option<&ClientFactory>> {
+        if self.ingest_parallelism <= 1 {
+            return Ok(None);
+        }
+
+        if self.table_options.temporary {
+            return Err(Error::with_message_and_status(
+                "[Clickhouse] Temporary tables are only visible to their own session and cannot be ingested in parallel",
+                Status::InvalidArguments,
+            ));
+        }
+
+        self.factory.as_ref().map(Some).ok_or_else(|| {
+            Error::with_message_and_status(
+                "[Clickhouse] Parallel ingest is not available on this connection",
+                Status::InvalidState,
+            )
+        })
+    }
+
+    /// Streams the blocks of `reader` into a single INSERT through the statement's connection.
+    async fn insert_sequential(
+        &self,
+        query: &str,
+        qid: Option<Qid>,
+        mut coalescer: BlockCoalescer,
+        reader: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<usize> {
+        insert_streamed(&self.client(), query, qid, async |insert| {
+            let mut num_rows = 0;
+            for batch in reader {
+                let record_batch = batch?;
+                num_rows += record_batch.num_rows();
+                for block in coalescer.push(record_batch)? {
+                    send_block(insert, block.block).await?;
+                }
+            }
+
+            if let Some(block) = coalescer.finish()? {
+                send_block(insert, block.block).await?;
+            }
+            Ok(num_rows)
+        })
+        .await
+    }
+
+    fn create_target_table(&self, target_table: &str, schema: &Schema) -> Result<()> {
+        let queries = match self.ingest_mode {
+            IngestMode::Create => vec![create_table_query(
+                target_table,
+                schema,
+                false,
+                &self.table_options,
+            )?],
+            IngestMode::Append => vec![],
+            IngestMode::Replace if self.table_options.temporary => vec![
+                format!("DROP TEMPORARY TABLE IF EXISTS {target_table}"),
+                create_table_query(target_table, schema, false, &self.table_options)?,
+            ],
+            IngestMode::Replace => vec![
+                format!("DROP TABLE IF EXISTS {target_table}"),
+                create_table_query(target_table, schema, false, &self.table_options)?,
+            ],
+            IngestMode::CreateAppend => vec![create_table_query(
+                target_table,
+                schema,
+                true,
+                &self.table_options,
+            )?],
+        };
+
+        for query in queries {
+            let interrupt = self.start_query(&query);
+            self.rt.block_on(interrupt.run(async {
+                self.client()
+                    .execute(&query, interrupt.qid())
+                    .await
+                    .map_err(|err| from_clickhouse_error("Failed to create target table", err))
+            }))?;
+        }
+
+        if self.table_options.temporary && self.ingest_mode != IngestMode::Append {
+            self.session_modified.store(true, Ordering::Relaxed);
+        }
+
+        Ok(())
     }
 }
 
+/// Fans the blocks of `reader` out to the connections of `insert`.
+async fn insert_parallel(
+    mut insert: ParallelInsert,
+    mut coalescer: BlockCoalescer,
+    reader: Box<dyn RecordBatchReader + Send>,
+) -> Result<usize> {
+    let mut num_rows = 0;
+    for batch in reader {
+        let record_batch = batch?;
+        num_rows += record_batch.num_rows();
+        for block in coalescer.push(record_batch)? {
+            insert.send(block).await?;
+        }
+    }
+
+    if let Some(block) = coalescer.finish()? {
+        insert.send(block).await?;
+    }
+    insert.finish().await?;
+
+    Ok(num_rows)
+}
+
 impl Optionable for ClickhouseStatement {
     type Option = OptionStatement;
 
@@ -42,6 +489,73 @@ impl Optionable for ClickhouseStatement {
         key: Self::Option,
         value: adbc_core::options::OptionValue,
     ) -> Result<()> {
+        if key.as_ref() == OPTION_QUERY_TIMEOUT_MS {
+            self.query_timeout = parse_timeout_option(key.as_ref(), value)?;
+            return Ok(());
+        }
+
+        if let Some(name) = setting_name(key.as_ref()) {
+            let name = name?;
+            return match value {
+                OptionValue::String(value) => {
+                    self.settings.insert(name.to_string(), value);
+                    Ok(())
+                }
+                _ => Err(Error::with_message_and_status(
+                    format!("[Clickhouse] Value is not a string, key: {key:?}"),
+                    Status::InvalidArguments,
+                )),
+            };
+        }
+
+        if let Some(clause) = self.table_options.clause_mut(key.as_ref()) {
+            return match value {
+                OptionValue::String(value) => {
+                    *clause = Some(value).filter(|value| !value.is_empty());
+                    Ok(())
+                }
+                _ => Err(Error::with_message_and_status(
+                    format!("[Clickhouse] Value is not a string, key: {key:?}"),
+                    Status::InvalidArguments,
+                )),
+            };
+        }
+
+        if let Some(limit) = self.block_size.limit_mut(key.as_ref()) {
+            *limit = match value {
+                OptionValue::String(value) => parse_number_option(key.as_ref(), &value)?,
+                OptionValue::Int(value) => parse_number_option(key.as_ref(), &value.to_string())?,
+                _ => {
+                    return Err(Error::with_message_and_status(
+                        format!("[Clickhouse] Value is not a string or integer, key: {key:?}"),
+                        Status::InvalidArguments,
+                    ));
+                }
+            };
+            return Ok(());
+        }
+
+        if key.as_ref() == STATEMENT_OPTION_INGEST_PARALLELISM {
+            let parallelism = match value {
+                OptionValue::String(value) => parse_number_option(key.as_ref(), &value)?,
+                OptionValue::Int(value) => parse_number_option(key.as_ref(), &value.to_string())?,
+                _ => {
+                    return Err(Error::with_message_and_status(
+                        format!("[Clickhouse] Value is not a string or integer, key: {key:?}"),
+                        Status::InvalidArguments,
+                    ));
+                }
+            };
+            if parallelism == 0 {
+                return Err(Error::with_message_and_status(
+                    format!("[Clickhouse] {key:?} must be at least 1"),
+                    Status::InvalidArguments,
+                ));
+            }
+            self.ingest_parallelism = parallelism;
+            return Ok(());
+        }
+
         match key.as_ref() {
             constants::ADBC_INGEST_OPTION_TARGET_TABLE => match value {
                 OptionValue::String(value) => {
@@ -53,6 +567,46 @@ impl Optionable for ClickhouseStatement {
                     Status::InvalidArguments,
                 )),
             },
+            constants::ADBC_INGEST_OPTION_TARGET_CATALOG => match value {
+                OptionValue::String(value) => {
+                    self.ingest_target_catalog = Some(value).filter(|value| !value.is_empty());
+                    Ok(())
+                }
+                _ => Err(Error::with_message_and_status(
+                    "[Clickhouse] IngestOptionTargetCatalog value must be of type String",
+                    Status::InvalidArguments,
+                )),
+            },
+            constants::ADBC_INGEST_OPTION_TARGET_DB_SCHEMA => match value {
+                OptionValue::String(value) => {
+                    self.ingest_target_db_schema = Some(value).filter(|value| !value.is_empty());
+                    Ok(())
+                }
+                _ => Err(Error::with_message_and_status(
+                    "[Clickhouse] IngestOptionTargetDbSchema value must be of type String",
+                    Status::InvalidArguments,
+                )),
+            },
+            constants::ADBC_INGEST_OPTION_TEMPORARY => match value {
+                OptionValue::String(value) => {
+                    self.table_options.temporary = parse_bool_option(key.as_ref(), &value)?;
+                    Ok(())
+                }
+                _ => Err(Error::with_message_and_status(
+                    "[Clickhouse] IngestOptionTemporary value must be of type String",
+                    Status::InvalidArguments,
+                )),
+            },
+            constants::ADBC_INGEST_OPTION_MODE => match value {
+                OptionValue::String(value) => {
+                    self.ingest_mode = IngestMode::parse(&value)?;
+                    Ok(())
+                }
+                _ => Err(Error::with_message_and_status(
+                    "[Clickhouse] IngestOptionMode value must be of type String",
+                    Status::InvalidArguments,
+                )),
+            },
             _ => Err(Error::with_message_and_status(
                 format!("[Clickhouse] Unrecognized option: {key:?}"),
                 Status::NotFound,
@@ -61,7 +615,28 @@ impl Optionable for ClickhouseStatement {
     }
 
     fn get_option_string(&self, key: Self::Option) -> Result<String> {
+        if let Some(name) = setting_name(key.as_ref())
+            && let Some(value) = self.settings.get(name?)
+        {
+            return Ok(value.clone());
+        }
+
+        if let Some(clause) = self.table_options.clause(key.as_ref()) {
+            return clause.clone().ok_or_else(|| {
+                Error::with_message_and_status(
+                    format!("[Clickhouse] {key:?} has not been set"),
+                    Status::NotFound,
+                )
+            });
+        }
+
+        if let Some(limit) = self.block_size.limit(key.as_ref()) {
+            return Ok(limit.to_string());
+        }
+
         match key.as_ref() {
+            OPTION_QUERY_TIMEOUT_MS => Ok(timeout_millis(self.query_timeout).to_string()),
+            STATEMENT_OPTION_INGEST_PARALLELISM => Ok(self.ingest_parallelism.to_string()),
             constants::ADBC_INGEST_OPTION_TARGET_TABLE => {
                 let target_table = self.ingest_target_table.clone();
                 match target_table {
@@ -72,6 +647,24 @@ impl Optionable for ClickhouseStatement {
                     )),
                 }
             }
+            constants::ADBC_INGEST_OPTION_TARGET_CATALOG => {
+                self.ingest_target_catalog.clone().ok_or_else(|| {
+                    Error::with_message_and_status(
+                        format!("[Clickhouse] {key:?} has not been set"),
+                        Status::NotFound,
+                    )
+                })
+            }
+            constants::ADBC_INGEST_OPTION_TARGET_DB_SCHEMA => {
+                self.ingest_target_db_schema.clone().ok_or_else(|| {
+                    Error::with_message_and_status(
+                        format!("[Clickhouse] {key:?} has not been set"),
+                        Status::NotFound,
+                    )
+                })
+            }
+            constants::ADBC_INGEST_OPTION_TEMPORARY => Ok(self.table_options.temporary.to_string()),
+            constants::ADBC_INGEST_OPTION_MODE => Ok(self.ingest_mode.to_string()),
             _ => Err(Error::with_message_and_status(
                 format!("[Clickhouse] Unrecognized option: {key:?}"),
                 Status::NotFound,
@@ -87,17 +680,28 @@ impl Optionable for ClickhouseStatement {
     }
 
     fn get_option_int(&self, key: Self::Option) -> Result<i64> {
-        Err(Error::with_message_and_status(
-            format!("[Clickhouse] Unrecognized option: {key:?}"),
-            Status::NotFound,
-        ))
+        if let Some(limit) = self.block_size.limit(key.as_ref()) {
+            return Ok(limit as i64);
+        }
+
+        match key.as_ref() {
+            OPTION_QUERY_TIMEOUT_MS => Ok(timeout_millis(self.query_timeout) as i64),
+            STATEMENT_OPTION_INGEST_PARALLELISM => Ok(self.ingest_parallelism as i64),
+            _ => Err(Error::with_message_and_status(
+                format!("[Clickhouse] Unrecognized option: {key:?}"),
+                Status::NotFound,
+            )),
+        }
     }
 
     fn get_option_double(&self, key: Self::Option) -> Result<f64> {
-        Err(Error::with_message_and_status(
-            format!("[Clickhouse] Unrecognized option: {key:?}"),
-            Status::NotFound,
-        ))
+        match key.as_ref() {
+            OPTION_QUERY_TIMEOUT_MS => Ok(timeout_millis(self.query_timeout) as f64),
+            _ => Err(Error::with_message_and_status(
+                format!("[Clickhouse] Unrecognized option: {key:?}"),
+                Status::NotFound,
+            )),
+        }
     }
 }
 
@@ -113,13 +717,36 @@ impl Statement for ClickhouseStatement {
     }
 
     fn execute(&mut self) -> Result<impl RecordBatchReader + Send> {
+        self.check_prepared()?;
+
+        let params = match self.bound_params()? {
+            Some(rows) if rows.len() != 1 => {
+                return Err(Error::with_message_and_status(
+                    format!(
+                        "[Clickhouse] A query returning results takes exactly one row of parameters, got {}; use execute_update to run a statement once per row",
+                        rows.len()
+                    ),
+                    Status::InvalidArguments,
+                ));
+            }
+            Some(mut rows) => rows.pop(),
+            None => None,
+        };
+
         if let Some(query) = &self.sql_query {
-            let response = self
-                .rt
-                .block_on(self.conn.query(query, None))
-                .map_err(|err| from_clickhouse_error("Failed to execute query", err))?;
+            self.track_session_change(query);
+            let interrupt = self.start_query(query);
+            let describe_params = params.clone();
+            let response = self.rt.block_on(interrupt.run(async {
+                self.client()
+                    .query_params(query, params, interrupt.qid())
+                    .await
+                    .map_err(|err| from_clickhouse_error("Failed to execute query", err))
+            }))?;
 
-            Ok(ClickhouseReader::new(self.rt.clone(), response))
+            ClickhouseReader::try_new(self.rt.clone(), response, interrupt, |interrupt| {
+                self.empty_result_schema(query, describe_params, interrupt)
+            })
         } else {
             Err(Error::with_message_and_status(
                 "[Clickhouse] SQL query is empty",
@@ -129,48 +756,58 @@ impl Statement for ClickhouseStatement {
     }
 
     fn execute_update(&mut self) -> Result<Option<i64>> {
-        if let Some(sql) = &self.sql_query {
-            self.rt
-                .block_on(self.conn.execute(sql, None))
-                .map_err(|err| from_clickhouse_error("Failed to execute update", err))?;
-        } else if let Some(record_batch) = self.bound_record_batch.take()
-            && let Some(target_table) = &self.ingest_target_table
-        {
-            let _ = self
-                .rt
-                .block_on(self.conn.insert(
-                    format!("INSERT INTO {target_table} FORMAT Native"),
-                    record_batch,
-                    None,
-                ))
-                .map_err(|err| from_clickhouse_error("Failed to execute update", err))?;
-        } else if let Some(reader) = self.bound_record_batch_reader.take()
-            && let Some(target_table) = &self.ingest_target_table
-        {
-            let query = format!("INSERT INTO {target_table} FORMAT Native");
-
-            self.rt.block_on(async {
-                for batch in reader {
-                    let record_batch = batch?;
-                    let _ = self
-                        .conn
-                        .insert(&query, record_batch, None)
-                        .await
-                        .map_err(|err| from_clickhouse_error("Failed to execute update", err))?;
-                }
+        self.check_prepared()?;
 
-                Result::Ok(())
-            })?;
-        }
+        if let Some(query) = self.sql_query.clone() {
+            let rows = match self.bound_params()? {
+                Some(rows) => rows.into_iter().map(Some).collect(),
+                None => vec![None],
+            };
 
-        Ok(Some(0))
+            self.track_session_change(&query);
+            let interrupt = self.start_query(&query);
+            let client = self.client();
+            let ((), written_rows) = self.rt.block_on(interrupt.run(async {
+                let execute = async {
+                    for params in rows {
+                        client
+                            .execute_params(&query, params, interrupt.qid())
+                            .await
+                            .map_err(|err| {
+                                from_clickhouse_error("Failed to execute update", err)
+                            })?;
+                    }
+
+                    Result::Ok(())
+                };
+                track_written_rows(&client, interrupt.qid(), execute).await
+            }))?;
+
+            // DDL and the like write nothing and report no count, neither does a query whose
+            // progress was partly missed.
+            match written_rows {
+                Some(written_rows) if written_rows > 0 || is_insert(&query) => {
+                    Ok(Some(written_rows as i64))
+                }
+                _ => Ok(None),
+            }
+        } else if let Some(target_table) = self.ingest_target_table.clone()
+            && let Some(reader) = self.take_bound_data()
+        {
+            let target_table = self.ingest_target(&target_table)?;
+            self.ingest(&target_table, reader)
+                .map(|num_rows| Some(num_rows as i64))
+        } else {
+            Err(Error::with_message_and_status(
+                "[Clickhouse] Statement has no query or bound data",
+                Status::InvalidState,
+            ))
+        }
     }
 
     fn execute_schema(&mut self) -> Result<arrow_schema::Schema> {
-        Err(Error::with_message_and_status(
-            "[Clickhouse] ExecuteSchema not implemented".to_string(),
-            Status::NotImplemented,
-        ))
+        self.check_prepared()?;
+        self.query_schema()
     }
 
     fn execute_partitions(&mut self) -> Result<adbc_core::PartitionedResult> {
@@ -181,17 +818,56 @@ impl Statement for ClickhouseStatement {
     }
 
     fn get_parameter_schema(&self) -> Result<arrow_schema::Schema> {
-        Err(Error::with_message_and_status(
-            "[Clickhouse] GetParameterSchema not implemented".to_string(),
-            Status::NotImplemented,
-        ))
+        match &self.sql_query {
+            Some(query) => match &self.prepared {
+                Some(prepared) if &prepared.query == query => Ok(prepared.parameter_schema.clone()),
+                _ => parameter_schema(query),
+            },
+            None => Err(Error::with_message_and_status(
+                "[Clickhouse] SQL query is empty",
+                Status::InvalidState,
+            )),
+        }
     }
 
     fn prepare(&mut self) -> Result<()> {
-        Err(Error::with_message_and_status(
-            "[Clickhouse] Prepare not implemented".to_string(),
-            Status::NotImplemented,
-        ))
+        let Some(query) = self.sql_query.clone() else {
+            return Err(Error::with_message_and_status(
+                "[Clickhouse] SQL query is empty",
+                Status::InvalidState,
+            ));
+        };
+        let parameter_schema = parameter_schema(&query)?;
+
+        // Placeholders are replaced by default values so the server can check the query before
+        // any parameters are bound.
+        let substituted = substitute_placeholders(&query);
+        let schema = if returns_rows(&query) && !has_identifier_placeholders(&query) {
+            Some(self.describe(&substituted, None)?)
+        } else {
+            // Identifier placeholders were replaced by their names, which need not name anything
+            // that exists, so such a query is only checked for syntax.
+            let explain = if returns_rows(&query) {
+                format!("EXPLAIN SYNTAX {substituted}")
+            } else {
+                format!("EXPLAIN AST {substituted}")
+            };
+            let interrupt = self.start_query(&explain);
+            self.rt.block_on(interrupt.run(async {
+                self.client()
+                    .execute(&explain, interrupt.qid())
+                    .await
+                    .map_err(|err| from_clickhouse_error("Failed to prepare query", err))
+            }))?;
+            None
+        };
+
+        self.prepared = Some(PreparedQuery {
+            query,
+            schema,
+            parameter_schema,
+        });
+        Ok(())
     }
 
     fn set_sql_query(&mut self, query: impl AsRef<str>) -> Result<()> {
@@ -207,9 +883,156 @@ impl Statement for ClickhouseStatement {
     }
 
     fn cancel(&mut self) -> Result<()> {
-        Err(Error::with_message_and_status(
-            "[Clickhouse] Cancel not implemented".to_string(),
-            Status::NotImplemented,
-        ))
+        self.cancel.cancel(&self.rt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use adbc_core::{Connection, Database, Optionable, options::OptionDatabase};
+    use arrow_array::{Int32Array, RecordBatch};
+    use arrow_schema::{DataType, Field};
+
+    use super::*;
+    use crate::{
+        ClickhouseDatabase,
+        stand_in::{self, EXCEPTION_MESSAGE, Reply},
+    };
+
+    #[test]
+    fn runs_queries_after_an_ingest_timed_out() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let (endpoint, _) = stand_in::start(&server, |query| {
+            if query.starts_with("CREATE") {
+                Reply::EndOfStream
+            } else if query.starts_with("INSERT") {
+                Reply::AwaitCancel
+            } else {
+                Reply::Exception(1000)
+            }
+        });
+
+        let mut database = ClickhouseDatabase::default();
+        database
+            .set_option(OptionDatabase::Uri, endpoint.into())
+            .unwrap();
+        database
+            .set_option(OPTION_QUERY_TIMEOUT_MS.into(), "300".into())
+            .unwrap();
+        let mut connection = database.new_connection().unwrap();
+        let mut statement = connection.new_statement().unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2]))]).unwrap();
+        statement
+            .set_option(OptionStatement::TargetTable, "t".into())
+            .unwrap();
+        statement.bind(batch).unwrap();
+        let err = statement.execute_update().unwrap_err();
+        assert_eq!(err.status, Status::Timeout, "{err:?}");
+
+        // The INSERT was cancelled, so the connection answers the next query.
+        statement.set_sql_query("SELECT 1").unwrap();
+        let err = statement.execute_update().unwrap_err();
+        assert!(err.message.contains(EXCEPTION_MESSAGE), "{err:?}");
+    }
+
+    #[test]
+    fn sends_statement_settings_with_every_query() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let (endpoint, queries) = stand_in::start(&server, |query| {
+            if query.contains("FORMAT Native") {
+                Reply::AwaitCancel
+            } else {
+                Reply::EndOfStream
+            }
+        });
+
+        let mut database = ClickhouseDatabase::default();
+        database
+            .set_option(OptionDatabase::Uri, endpoint.into())
+            .unwrap();
+        database
+            .set_option(OPTION_QUERY_TIMEOUT_MS.into(), "300".into())
+            .unwrap();
+        let mut connection = database.new_connection().unwrap();
+        let mut statement = connection.new_statement().unwrap();
+        statement
+            .set_option("clickhouse.setting.max_threads".into(), "4".into())
+            .unwrap();
+        // Connecting read the server version without the statement settings.
+        queries.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2]))]).unwrap();
+        statement
+            .set_option(OptionStatement::TargetTable, "t".into())
+            .unwrap();
+        statement.bind(batch.clone()).unwrap();
+        let err = statement.execute_update().unwrap_err();
+        assert_eq!(err.status, Status::Timeout, "{err:?}");
+
+        // Neither statement takes a SETTINGS clause after its query.
+        statement
+            .set_sql_query("CREATE TABLE u ENGINE = Memory AS SELECT 1 AS a")
+            .unwrap();
+        statement.execute_update().unwrap();
+        statement
+            .set_sql_query("INSERT INTO t SETTINGS async_insert = 1 SELECT {a:Int32}")
+            .unwrap();
+        statement.bind(batch).unwrap();
+        statement.execute_update().unwrap();
+
+        // The CREATE and INSERT of the ingest, the CREATE and one INSERT per bound row.
+        let queries = queries.try_iter().collect::<Vec<_>>();
+        assert_eq!(queries.len(), 5, "{queries:?}");
+        for query in queries {
+            assert!(query.contains("max_threads"), "{query}");
+            assert!(query.contains("max_execution_time"), "{query}");
+            assert!(!query.contains("SETTINGS max_threads"), "{query}");
+        }
+    }
+
+    #[test]
+    fn cancels_the_running_query_on_its_connection() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let (endpoint, queries) = stand_in::start(&server, |query| {
+            if query.starts_with("SELECT sleep") {
+                Reply::AwaitCancel
+            } else {
+                Reply::Exception(1000)
+            }
+        });
+
+        let mut database = ClickhouseDatabase::default();
+        database
+            .set_option(OptionDatabase::Uri, endpoint.into())
+            .unwrap();
+        database
+            .set_option(OPTION_QUERY_TIMEOUT_MS.into(), "3000".into())
+            .unwrap();
+        let mut connection = database.new_connection().unwrap();
+        let mut statement = connection.new_statement().unwrap();
+
+        let query = std::thread::spawn(move || {
+            statement.set_sql_query("SELECT sleep(3)").unwrap();
+            let err = statement.execute_update().unwrap_err();
+            (statement, err)
+        });
+        while !queries
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .contains("SELECT sleep")
+        {}
+        connection.cancel().unwrap();
+        let (mut statement, err) = query.join().unwrap();
+        assert_eq!(err.status, Status::Cancelled, "{err:?}");
+
+        // The server ended the query on the Cancel packet, so the connection answers the next one.
+        statement.set_sql_query("SELECT 1").unwrap();
+        let err = statement.execute_update().unwrap_err();
+        assert!(err.message.contains(EXCEPTION_MESSAGE), "{err:?}");
     }
 }
@@ -1,13 +1,44 @@
+mod blocks;
+mod cancel;
+mod ddl;
+mod factory;
 mod get_info;
 mod get_objects;
+mod hosts;
+mod ingest;
+mod params;
+mod placeholders;
+mod prepare;
+mod progress;
+mod quote;
+mod settings;
+mod timeout;
+mod tls;
+mod uri;
 
 use std::borrow::Cow;
 
-use clickhouse_arrow::{ClickHouseResponse, NativeClient, QueryParams, SettingValue};
+use arrow_array::{BinaryArray, StringArray};
+use clickhouse_arrow::{ArrowClient, ClickHouseResponse, Qid, QueryParams, SettingValue};
 use futures::StreamExt;
 
+pub(crate) use blocks::*;
+pub(crate) use cancel::*;
+pub(crate) use ddl::*;
+pub(crate) use factory::*;
 pub(crate) use get_info::*;
 pub(crate) use get_objects::*;
+pub(crate) use hosts::*;
+pub(crate) use ingest::*;
+pub(crate) use params::*;
+pub(crate) use placeholders::*;
+pub(crate) use prepare::*;
+pub(crate) use progress::*;
+pub(crate) use quote::*;
+pub(crate) use settings::*;
+pub(crate) use timeout::*;
+pub(crate) use tls::*;
+pub(crate) use uri::*;
 
 pub enum Runtime {
     Handle(tokio::runtime::Handle),
@@ -15,13 +46,15 @@ pub enum Runtime {
 }
 
 impl Runtime {
-    pub fn new() -> std::io::Result<Self> {
+    pub fn new(worker_threads: Option<usize>) -> std::io::Result<Self> {
         if let Ok(handle) = tokio::runtime::Handle::try_current() {
             Ok(Self::Handle(handle))
         } else {
-            let rt = tokio::runtime::Builder::new_multi_thread()
-                .enable_all()
-                .build()?;
+            let mut builder = tokio::runtime::Builder::new_multi_thread();
+            if let Some(worker_threads) = worker_threads {
+                builder.worker_threads(worker_threads);
+            }
+            let rt = builder.enable_all().build()?;
 
             Ok(Self::TokioRuntime(rt))
         }
@@ -33,6 +66,14 @@ impl Runtime {
             Runtime::TokioRuntime(runtime) => runtime.block_on(fut),
         }
     }
+
+    /// Runs `fut` in the background, detached from the caller.
+    pub fn spawn<F: Future<Output = ()> + Send + 'static>(&self, fut: F) {
+        match self {
+            Runtime::Handle(handle) => drop(handle.spawn(fut)),
+            Runtime::TokioRuntime(runtime) => drop(runtime.spawn(fut)),
+        }
+    }
 }
 
 #[derive(clickhouse_arrow::Row)]
@@ -70,16 +111,99 @@ pub(crate) struct ColumnRow {
     pub xdbc_is_generatedcolumn: bool,
 }
 
+/// Status and SQLSTATE of the ClickHouse exception codes callers are expected to act upon, see
+/// `src/Common/ErrorCodes.cpp` in the ClickHouse repository.
+const SERVER_ERROR_CODES: &[(i32, adbc_core::error::Status, &str)] = &[
+    // TABLE_ALREADY_EXISTS
+    (57, adbc_core::error::Status::AlreadyExists, "42S01"),
+    // UNKNOWN_TABLE
+    (60, adbc_core::error::Status::NotFound, "42S02"),
+    // SYNTAX_ERROR
+    (62, adbc_core::error::Status::InvalidArguments, "42000"),
+    // UNKNOWN_DATABASE
+    (81, adbc_core::error::Status::NotFound, "3F000"),
+    // DATABASE_ALREADY_EXISTS
+    (82, adbc_core::error::Status::AlreadyExists, "42S01"),
+    // TIMEOUT_EXCEEDED
+    (159, adbc_core::error::Status::Timeout, "HYT00"),
+    // UNKNOWN_USER
+    (192, adbc_core::error::Status::Unauthenticated, "28000"),
+    // WRONG_PASSWORD
+    (193, adbc_core::error::Status::Unauthenticated, "28000"),
+    // REQUIRED_PASSWORD
+    (194, adbc_core::error::Status::Unauthenticated, "28000"),
+    // QUERY_WAS_CANCELLED
+    (394, adbc_core::error::Status::Cancelled, "HY008"),
+    // ACCESS_DENIED
+    (497, adbc_core::error::Status::Unauthorized, "42501"),
+    // AUTHENTICATION_FAILED
+    (516, adbc_core::error::Status::Unauthenticated, "28000"),
+];
+
 pub(crate) fn from_clickhouse_error(
     context: impl AsRef<str>,
     error: clickhouse_arrow::Error,
 ) -> adbc_core::error::Error {
     let context = context.as_ref();
     match &error {
-        clickhouse_arrow::Error::Io(_) => adbc_core::error::Error::with_message_and_status(
-            format!("[Clickhouse] {context}: {}", error),
-            adbc_core::error::Status::IO,
-        ),
+        clickhouse_arrow::Error::ServerException(server_error) => {
+            let (status, sqlstate) = SERVER_ERROR_CODES
+                .iter()
+                .find(|(code, _, _)| *code == server_error.code)
+                .map_or(
+                    (adbc_core::error::Status::Internal, "HY000"),
+                    |(_, status, sqlstate)| (*status, *sqlstate),
+                );
+
+            let mut adbc_error = adbc_core::error::Error::with_message_and_status(
+                format!(
+                    "[Clickhouse] {context}: {} ({}): {}",
+                    server_error.name, server_error.code, server_error.message
+                ),
+                status,
+            );
+            adbc_error.vendor_code = server_error.code;
+            adbc_error.sqlstate = sqlstate_chars(sqlstate);
+            push_error_detail(
+                &mut adbc_error,
+                "clickhouse.exception_name",
+                &server_error.name,
+            );
+            push_error_detail(
+                &mut adbc_error,
+                "clickhouse.exception_code",
+                &server_error.code.to_string(),
+            );
+            if !server_error.stack_trace.is_empty() {
+                push_error_detail(
+                    &mut adbc_error,
+                    "clickhouse.stack_trace",
+                    &server_error.stack_trace,
+                );
+            }
+            adbc_error
+        }
+        clickhouse_arrow::Error::Io(_)
+        | clickhouse_arrow::Error::ConnectionGone(_)
+        | clickhouse_arrow::Error::InternalChannelError => {
+            adbc_core::error::Error::with_message_and_status(
+                format!("[Clickhouse] {context}: {}", error),
+                adbc_core::error::Status::IO,
+            )
+        }
+        clickhouse_arrow::Error::ConnectionTimeout(_) => {
+            adbc_core::error::Error::with_message_and_status(
+                format!("[Clickhouse] {context}: {}", error),
+                adbc_core::error::Status::Timeout,
+            )
+        }
+        clickhouse_arrow::Error::MissingConnectionInformation
+        | clickhouse_arrow::Error::MalformedConnectionInformation(_) => {
+            adbc_core::error::Error::with_message_and_status(
+                format!("[Clickhouse] {context}: {}", error),
+                adbc_core::error::Status::InvalidArguments,
+            )
+        }
         _ => adbc_core::error::Error::with_message_and_status(
             format!("[Clickhouse] {context}: {}", error),
             adbc_core::error::Status::Internal,
@@ -87,6 +211,89 @@ pub(crate) fn from_clickhouse_error(
     }
 }
 
+/// Adds an ADBC 1.1 error detail, a UTF-8 value under a `clickhouse.` key.
+pub(crate) fn push_error_detail(error: &mut adbc_core::error::Error, key: &str, value: &str) {
+    error
+        .details
+        .get_or_insert_with(Vec::new)
+        .push((key.to_string(), value.as_bytes().to_vec()));
+}
+
+/// Longest SQL text attached to an error, longer queries are cut at this many bytes.
+const MAX_QUERY_DETAIL_LEN: usize = 4096;
+
+/// What a failed query was, attached to its errors as ADBC 1.1 error details.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct QueryDetails {
+    pub query_id: Option<String>,
+    pub query: Option<String>,
+    pub endpoint: Option<String>,
+}
+
+impl QueryDetails {
+    pub fn new(query: &str, endpoint: Option<&str>) -> Self {
+        let query = if query.len() > MAX_QUERY_DETAIL_LEN {
+            let end = (0..=MAX_QUERY_DETAIL_LEN)
+                .rev()
+                .find(|end| query.is_char_boundary(*end))
+                .unwrap_or(0);
+            format!("{}...", &query[..end])
+        } else {
+            query.to_string()
+        };
+
+        Self {
+            query_id: None,
+            query: Some(query),
+            endpoint: endpoint.map(str::to_string),
+        }
+    }
+
+    pub fn attach(&self, mut error: adbc_core::error::Error) -> adbc_core::error::Error {
+        if let Some(query_id) = &self.query_id {
+            push_error_detail(&mut error, "clickhouse.query_id", query_id);
+        }
+        if let Some(query) = &self.query {
+            push_error_detail(&mut error, "clickhouse.query", query);
+        }
+        if let Some(endpoint) = &self.endpoint {
+            push_error_detail(&mut error, "clickhouse.endpoint", endpoint);
+        }
+        error
+    }
+}
+
+fn sqlstate_chars(sqlstate: &str) -> [std::ffi::c_char; 5] {
+    let mut chars = [0; 5];
+    for (c, b) in chars.iter_mut().zip(sqlstate.bytes()) {
+        *c = b as std::ffi::c_char;
+    }
+    chars
+}
+
+pub(crate) fn parse_bool_option(key: &str, value: &str) -> adbc_core::error::Result<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(adbc_core::error::Error::with_message_and_status(
+            format!("[Clickhouse] Invalid boolean value for {key}: {value}"),
+            adbc_core::error::Status::InvalidArguments,
+        )),
+    }
+}
+
+pub(crate) fn parse_number_option<T: std::str::FromStr>(
+    key: &str,
+    value: &str,
+) -> adbc_core::error::Result<T> {
+    value.parse::<T>().map_err(|_| {
+        adbc_core::error::Error::with_message_and_status(
+            format!("[Clickhouse] Invalid numeric value for {key}: {value}"),
+            adbc_core::error::Status::InvalidArguments,
+        )
+    })
+}
+
 const FETCH_ALL_BASE_SQL: &str = "SELECT
     c.table_catalog,
 	c.table_schema,
@@ -127,229 +334,276 @@ const FETCH_MIN_SCHEMA_BASE_SQL: &str = "SELECT
 FROM
 	INFORMATION_SCHEMA.SCHEMATA s";
 
-pub(crate) trait NativeClientExt {
-    fn fetch_min_schemas(
-        &self,
-        catalog_filter: Option<String>,
-        schema_filter: Option<String>,
-    ) -> impl Future<Output = Result<ClickHouseResponse<SchemaRow>, clickhouse_arrow::Error>> + Send;
+/// Query listing the databases matching the filters, as `SchemaRow`s.
+pub(crate) fn min_schemas_query(
+    catalog_filter: Option<String>,
+    schema_filter: Option<String>,
+) -> (String, Option<QueryParams>) {
+    let mut pred: Vec<Cow<'static, str>> = vec![];
+    let mut params = vec![];
+
+    if let Some(catalog_filter) = catalog_filter {
+        pred.push("s.catalog_name LIKE {catalog_filter:String}".into());
+        params.push((
+            "catalog_filter".to_string(),
+            SettingValue::String(catalog_filter),
+        ));
+    }
 
-    fn fetch_min_schema_tables(
-        &self,
-        catalog_filter: Option<String>,
-        schema_filter: Option<String>,
-        table_filter: Option<String>,
-        table_type_filter: Option<Vec<String>>,
-    ) -> impl Future<Output = Result<ClickHouseResponse<TableRow>, clickhouse_arrow::Error>> + Send;
+    if let Some(schema_filter) = schema_filter {
+        pred.push("s.schema_name LIKE {schema_filter:String}".into());
+        params.push((
+            "schema_filter".to_string(),
+            SettingValue::String(schema_filter),
+        ));
+    }
 
-    fn fetch_all(
-        &self,
-        catalog_filter: Option<String>,
-        schema_filter: Option<String>,
-        table_filter: Option<String>,
-        table_type_filter: Option<Vec<String>>,
-        column_filter: Option<String>,
-    ) -> impl Future<Output = Result<ClickHouseResponse<ColumnRow>, clickhouse_arrow::Error>> + Send;
+    if !pred.is_empty() {
+        let where_part: String = pred.join(" AND ");
 
-    fn fetch_version(
-        &self,
-    ) -> impl Future<Output = Result<Option<String>, clickhouse_arrow::Error>> + Send;
+        (
+            format!(
+                "{FETCH_MIN_SCHEMA_BASE_SQL}
+WHERE {where_part}"
+            ),
+            Some(QueryParams(params)),
+        )
+    } else {
+        (FETCH_MIN_SCHEMA_BASE_SQL.to_string(), None)
+    }
 }
 
-impl NativeClientExt for NativeClient {
-    async fn fetch_min_schemas(
-        &self,
-        catalog_filter: Option<String>,
-        schema_filter: Option<String>,
-    ) -> Result<ClickHouseResponse<SchemaRow>, clickhouse_arrow::Error> {
-        let mut pred: Vec<Cow<'static, str>> = vec![];
-        let mut params = vec![];
-
-        if let Some(catalog_filter) = catalog_filter {
-            pred.push("s.catalog_name LIKE {catalog_filter:String}".into());
-            params.push((
-                "catalog_filter".to_string(),
-                SettingValue::String(catalog_filter),
-            ));
-        }
+/// Query listing the tables matching the filters, as `TableRow`s.
+pub(crate) fn min_tables_query(
+    catalog_filter: Option<String>,
+    schema_filter: Option<String>,
+    table_filter: Option<String>,
+    table_type_filter: Option<Vec<String>>,
+) -> (String, Option<QueryParams>) {
+    let mut pred: Vec<Cow<'static, str>> = vec![];
+    let mut params = vec![];
+
+    if let Some(catalog_filter) = catalog_filter {
+        pred.push("t.table_catalog LIKE {catalog_filter:String}".into());
+        params.push((
+            "catalog_filter".to_string(),
+            SettingValue::String(catalog_filter),
+        ));
+    }
 
-        if let Some(schema_filter) = schema_filter {
-            pred.push("s.schema_name LIKE {schema_filter:String}".into());
-            params.push((
-                "schema_filter".to_string(),
-                SettingValue::String(schema_filter),
-            ));
-        }
+    if let Some(schema_filter) = schema_filter {
+        pred.push("t.table_schema LIKE {schema_filter:String}".into());
+        params.push((
+            "schema_filter".to_string(),
+            SettingValue::String(schema_filter),
+        ));
+    }
 
-        let (sql, params) = if !pred.is_empty() {
-            let where_part: String = pred.join(" AND ");
+    if let Some(table_filter) = table_filter {
+        pred.push("t.table_name LIKE {table_filter:String}".into());
+        params.push((
+            "table_filter".to_string(),
+            SettingValue::String(table_filter),
+        ));
+    }
 
-            (
-                format!(
-                    "{FETCH_MIN_SCHEMA_BASE_SQL}
+    if let Some(table_type_filter) = table_type_filter
+        && !table_type_filter.is_empty()
+    {
+        let mut idents = vec![];
+        table_type_filter
+            .into_iter()
+            .enumerate()
+            .for_each(|(i, v)| {
+                idents.push(format!("{{table_type_filter_{i}:String}}"));
+                params.push((format!("table_type_filter_{i}"), SettingValue::String(v)));
+            });
+
+        let idents = idents.join(",");
+        pred.push(format!("t.table_type IN ({idents})").into());
+    }
+
+    if !pred.is_empty() {
+        let where_part: String = pred.join(" AND ");
+
+        (
+            format!(
+                "{FETCH_MIN_TABLE_BASE_SQL}
 WHERE {where_part}"
-                ),
-                Some(QueryParams(params)),
-            )
-        } else {
-            (FETCH_MIN_SCHEMA_BASE_SQL.to_string(), None)
-        };
+            ),
+            Some(QueryParams(params)),
+        )
+    } else {
+        (FETCH_MIN_TABLE_BASE_SQL.to_string(), None)
+    }
+}
 
-        self.query_params::<SchemaRow>(sql, params, None).await
+/// Query listing the columns of the tables matching the filters, as `ColumnRow`s.
+pub(crate) fn columns_query(
+    catalog_filter: Option<String>,
+    schema_filter: Option<String>,
+    table_filter: Option<String>,
+    table_type_filter: Option<Vec<String>>,
+    column_filter: Option<String>,
+) -> (String, Option<QueryParams>) {
+    let mut pred: Vec<Cow<'static, str>> = vec![];
+    let mut params = vec![];
+
+    if let Some(catalog_filter) = catalog_filter {
+        pred.push("c.table_catalog LIKE {catalog_filter:String}".into());
+        params.push((
+            "catalog_filter".to_string(),
+            SettingValue::String(catalog_filter),
+        ));
     }
 
-    async fn fetch_min_schema_tables(
-        &self,
-        catalog_filter: Option<String>,
-        schema_filter: Option<String>,
-        table_filter: Option<String>,
-        table_type_filter: Option<Vec<String>>,
-    ) -> Result<ClickHouseResponse<TableRow>, clickhouse_arrow::Error> {
-        let mut pred: Vec<Cow<'static, str>> = vec![];
-        let mut params = vec![];
-
-        if let Some(catalog_filter) = catalog_filter {
-            pred.push("t.table_catalog LIKE {catalog_filter:String}".into());
-            params.push((
-                "catalog_filter".to_string(),
-                SettingValue::String(catalog_filter),
-            ));
-        }
+    if let Some(schema_filter) = schema_filter {
+        pred.push("c.table_schema LIKE {schema_filter:String}".into());
+        params.push((
+            "schema_filter".to_string(),
+            SettingValue::String(schema_filter),
+        ));
+    }
 
-        if let Some(schema_filter) = schema_filter {
-            pred.push("t.table_schema LIKE {schema_filter:String}".into());
-            params.push((
-                "schema_filter".to_string(),
-                SettingValue::String(schema_filter),
-            ));
-        }
+    if let Some(table_filter) = table_filter {
+        pred.push("c.table_name LIKE {table_filter:String}".into());
+        params.push((
+            "table_filter".to_string(),
+            SettingValue::String(table_filter),
+        ));
+    }
 
-        if let Some(table_filter) = table_filter {
-            pred.push("t.table_name LIKE {table_filter:String}".into());
-            params.push((
-                "table_filter".to_string(),
-                SettingValue::String(table_filter),
-            ));
-        }
+    if let Some(column_filter) = column_filter {
+        pred.push("c.column_name LIKE {column_filter:String}".into());
+        params.push((
+            "column_filter".to_string(),
+            SettingValue::String(column_filter),
+        ));
+    }
 
-        if let Some(table_type_filter) = table_type_filter
-            && !table_type_filter.is_empty()
-        {
-            let mut idents = vec![];
-            table_type_filter
-                .into_iter()
-                .enumerate()
-                .for_each(|(i, v)| {
-                    idents.push(format!("{{table_type_filter_{i}:String}}"));
-                    params.push((format!("table_type_filter_{i}"), SettingValue::String(v)));
-                });
-
-            let idents = idents.join(",");
-            pred.push(format!("t.table_type IN ({idents})").into());
-        }
+    if let Some(table_type_filter) = table_type_filter
+        && !table_type_filter.is_empty()
+    {
+        let mut idents = vec![];
+        table_type_filter
+            .into_iter()
+            .enumerate()
+            .for_each(|(i, v)| {
+                idents.push(format!("{{table_type_filter_{i}:String}}"));
+                params.push((format!("table_type_filter_{i}"), SettingValue::String(v)));
+            });
+
+        let idents = idents.join(",");
+        pred.push(format!("t.table_type IN ({idents})").into());
+    }
 
-        let (sql, params) = if !pred.is_empty() {
-            let where_part: String = pred.join(" AND ");
+    if !pred.is_empty() {
+        let where_part: String = pred.join(" AND ");
 
-            (
-                format!(
-                    "{FETCH_MIN_TABLE_BASE_SQL}
+        (
+            format!(
+                "{FETCH_ALL_BASE_SQL}
 WHERE {where_part}"
-                ),
-                Some(QueryParams(params)),
-            )
-        } else {
-            (FETCH_MIN_TABLE_BASE_SQL.to_string(), None)
-        };
-
-        self.query_params::<TableRow>(sql, params, None).await
+            ),
+            Some(QueryParams(params)),
+        )
+    } else {
+        (FETCH_ALL_BASE_SQL.to_string(), None)
     }
+}
 
-    async fn fetch_all(
+pub(crate) trait ArrowClientExt {
+    fn fetch_version(
         &self,
-        catalog_filter: Option<String>,
-        schema_filter: Option<String>,
-        table_filter: Option<String>,
-        table_type_filter: Option<Vec<String>>,
-        column_filter: Option<String>,
-    ) -> Result<ClickHouseResponse<ColumnRow>, clickhouse_arrow::Error> {
-        let mut pred: Vec<Cow<'static, str>> = vec![];
-        let mut params = vec![];
-
-        if let Some(catalog_filter) = catalog_filter {
-            pred.push("c.table_catalog LIKE {catalog_filter:String}".into());
-            params.push((
-                "catalog_filter".to_string(),
-                SettingValue::String(catalog_filter),
-            ));
-        }
-
-        if let Some(schema_filter) = schema_filter {
-            pred.push("c.table_schema LIKE {schema_filter:String}".into());
-            params.push((
-                "schema_filter".to_string(),
-                SettingValue::String(schema_filter),
-            ));
-        }
-
-        if let Some(table_filter) = table_filter {
-            pred.push("c.table_name LIKE {table_filter:String}".into());
-            params.push((
-                "table_filter".to_string(),
-                SettingValue::String(table_filter),
-            ));
-        }
+    ) -> impl Future<Output = Result<Option<String>, clickhouse_arrow::Error>> + Send;
 
-        if let Some(column_filter) = column_filter {
-            pred.push("c.column_name LIKE {column_filter:String}".into());
-            params.push((
-                "column_filter".to_string(),
-                SettingValue::String(column_filter),
-            ));
-        }
+    /// Names and ClickHouse types of the columns of `table`, a quoted table reference.
+    fn describe_table(
+        &self,
+        table: &str,
+        qid: Option<Qid>,
+    ) -> impl Future<Output = Result<Vec<(String, String)>, clickhouse_arrow::Error>> + Send;
 
-        if let Some(table_type_filter) = table_type_filter
-            && !table_type_filter.is_empty()
-        {
-            let mut idents = vec![];
-            table_type_filter
-                .into_iter()
-                .enumerate()
-                .for_each(|(i, v)| {
-                    idents.push(format!("{{table_type_filter_{i}:String}}"));
-                    params.push((format!("table_type_filter_{i}"), SettingValue::String(v)));
-                });
-
-            let idents = idents.join(",");
-            pred.push(format!("t.table_type IN ({idents})").into());
-        }
+    /// Names and ClickHouse types of the columns `query` returns with `params`, without running it.
+    fn describe_query(
+        &self,
+        query: &str,
+        params: Option<QueryParams>,
+        qid: Option<Qid>,
+    ) -> impl Future<Output = Result<Vec<(String, String)>, clickhouse_arrow::Error>> + Send;
+}
 
-        let (sql, params) = if !pred.is_empty() {
-            let where_part: String = pred.join(" AND ");
+impl ArrowClientExt for ArrowClient {
+    async fn fetch_version(&self) -> Result<Option<String>, clickhouse_arrow::Error> {
+        let batches = self
+            .query("SELECT version() as version", None)
+            .await?
+            .collect_all()
+            .await?;
+
+        let version = batches
+            .first()
+            .filter(|batch| batch.num_rows() > 0)
+            .and_then(|batch| string_value(batch.column(0).as_ref(), 0));
+
+        Ok(version)
+    }
 
-            (
-                format!(
-                    "{FETCH_ALL_BASE_SQL}
-WHERE {where_part}"
-                ),
-                Some(QueryParams(params)),
-            )
-        } else {
-            (FETCH_ALL_BASE_SQL.to_string(), None)
-        };
+    async fn describe_table(
+        &self,
+        table: &str,
+        qid: Option<Qid>,
+    ) -> Result<Vec<(String, String)>, clickhouse_arrow::Error> {
+        describe(self, table, None, qid).await
+    }
 
-        self.query_params::<ColumnRow>(sql, params, None).await
+    async fn describe_query(
+        &self,
+        query: &str,
+        params: Option<QueryParams>,
+        qid: Option<Qid>,
+    ) -> Result<Vec<(String, String)>, clickhouse_arrow::Error> {
+        // On separate lines so a trailing `--` comment in the query cannot swallow the parenthesis.
+        let query = query.trim_end().trim_end_matches(';').trim_end();
+        describe(self, &format!("(\n{query}\n)"), params, qid).await
     }
+}
 
-    async fn fetch_version(&self) -> Result<Option<String>, clickhouse_arrow::Error> {
-        #[derive(clickhouse_arrow::Row)]
-        struct ClickhouseVersion {
-            version: String,
-        }
+/// Runs `DESCRIBE TABLE` on `table`, a table reference or a parenthesized query using `params`.
+async fn describe(
+    client: &ArrowClient,
+    table: &str,
+    params: Option<QueryParams>,
+    qid: Option<Qid>,
+) -> Result<Vec<(String, String)>, clickhouse_arrow::Error> {
+    let batches = client
+        .query_params(format!("DESCRIBE TABLE {table}"), params, qid)
+        .await?
+        .collect_all()
+        .await?;
+
+    Ok(batches
+        .iter()
+        .flat_map(|batch| {
+            (0..batch.num_rows()).filter_map(|row| {
+                Some((
+                    string_value(batch.column(0).as_ref(), row)?,
+                    string_value(batch.column(1).as_ref(), row)?,
+                ))
+            })
+        })
+        .collect())
+}
 
-        self.query_one::<ClickhouseVersion>("SELECT version() as version", None)
-            .await
-            .map(|v| v.map(|v| v.version))
+/// Reads a `String` column value, which arrives as Utf8 or Binary depending on `ArrowOptions`.
+fn string_value(column: &dyn arrow_array::Array, row: usize) -> Option<String> {
+    let column = column.as_any();
+    if let Some(array) = column.downcast_ref::<StringArray>() {
+        Some(array.value(row).to_string())
+    } else {
+        column
+            .downcast_ref::<BinaryArray>()
+            .map(|array| String::from_utf8_lossy(array.value(row)).into_owned())
     }
 }
 
@@ -0,0 +1,307 @@
+use std::ops::RangeInclusive;
+
+use adbc_core::error::Result;
+use arrow_array::RecordBatch;
+use arrow_schema::SchemaRef;
+use arrow_select::concat::concat_batches;
+
+use crate::consts::{
+    STATEMENT_OPTION_INGEST_MIN_BLOCK_BYTES, STATEMENT_OPTION_INGEST_MIN_BLOCK_ROWS,
+};
+
+/// Size of the blocks bulk ingest sends, from the `clickhouse.ingest.min_block_*` statement
+/// options. Like the server's `min_insert_block_size_*` settings a block is complete once either
+/// limit is reached, zero disables a limit and bound batches are sent as is when both are zero.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BlockSize {
+    pub min_rows: usize,
+    pub min_bytes: usize,
+}
+
+impl BlockSize {
+    /// The limit set by the option `key`, `None` for other options.
+    pub fn limit(&self, key: &str) -> Option<usize> {
+        match key {
+            STATEMENT_OPTION_INGEST_MIN_BLOCK_ROWS => Some(self.min_rows),
+            STATEMENT_OPTION_INGEST_MIN_BLOCK_BYTES => Some(self.min_bytes),
+            _ => None,
+        }
+    }
+
+    pub fn limit_mut(&mut self, key: &str) -> Option<&mut usize> {
+        match key {
+            STATEMENT_OPTION_INGEST_MIN_BLOCK_ROWS => Some(&mut self.min_rows),
+            STATEMENT_OPTION_INGEST_MIN_BLOCK_BYTES => Some(&mut self.min_bytes),
+            _ => None,
+        }
+    }
+}
+
+/// A block of bulk ingest, as [`BlockCoalescer`] cut it from the bound batches.
+pub(crate) struct CoalescedBlock {
+    pub block: RecordBatch,
+    /// Indexes of the bound batches the rows come from, empty batches included in the count.
+    pub batches: RangeInclusive<usize>,
+}
+
+/// Concatenates small batches into blocks of [`BlockSize`] and splits larger ones, so every block
+/// but the last is about the target size.
+pub(crate) struct BlockCoalescer {
+    schema: SchemaRef,
+    size: BlockSize,
+    buffered: Vec<RecordBatch>,
+    buffered_rows: usize,
+    buffered_bytes: usize,
+    /// The first and last batch with buffered rows.
+    buffered_batches: Option<(usize, usize)>,
+    next_batch: usize,
+}
+
+impl BlockCoalescer {
+    pub fn new(schema: SchemaRef, size: BlockSize) -> Self {
+        Self {
+            schema,
+            size,
+            buffered: vec![],
+            buffered_rows: 0,
+            buffered_bytes: 0,
+            buffered_batches: None,
+            next_batch: 0,
+        }
+    }
+
+    /// Buffers `batch`, returning the blocks it completed.
+    pub fn push(&mut self, mut batch: RecordBatch) -> Result<Vec<CoalescedBlock>> {
+        let index = self.next_batch;
+        self.next_batch += 1;
+
+        let mut blocks = vec![];
+        // Slices share the buffers of the batch, their size is estimated from the row count.
+        let bytes_per_row = batch
+            .get_array_memory_size()
+            .div_ceil(batch.num_rows().max(1))
+            .max(1);
+
+        while batch.num_rows() > 0 {
+            let num_rows = batch.num_rows();
+            let missing_rows = self.missing_rows(bytes_per_row).min(num_rows);
+
+            let first_batch = self.buffered_batches.map_or(index, |(first, _)| first);
+            self.buffered_batches = Some((first_batch, index));
+            self.buffered_rows += missing_rows;
+            self.buffered_bytes += missing_rows * bytes_per_row;
+            if missing_rows == num_rows {
+                self.buffered.push(batch);
+                if self.is_full() {
+                    blocks.push(self.flush()?);
+                }
+                break;
+            }
+
+            self.buffered.push(batch.slice(0, missing_rows));
+            batch = batch.slice(missing_rows, num_rows - missing_rows);
+            blocks.push(self.flush()?);
+        }
+
+        Ok(blocks)
+    }
+
+    /// The last, possibly smaller, block.
+    pub fn finish(mut self) -> Result<Option<CoalescedBlock>> {
+        if self.buffered.is_empty() {
+            Ok(None)
+        } else {
+            self.flush().map(Some)
+        }
+    }
+
+    /// How many more rows of `bytes_per_row` complete the buffered block.
+    fn missing_rows(&self, bytes_per_row: usize) -> usize {
+        let by_rows = (self.size.min_rows > 0)
+            .then(|| self.size.min_rows.saturating_sub(self.buffered_rows).max(1));
+        let by_bytes = (self.size.min_bytes > 0).then(|| {
+            self.size
+                .min_bytes
+                .saturating_sub(self.buffered_bytes)
+                .div_ceil(bytes_per_row)
+                .max(1)
+        });
+
+        match (by_rows, by_bytes) {
+            (Some(by_rows), Some(by_bytes)) => by_rows.min(by_bytes),
+            (Some(missing_rows), None) | (None, Some(missing_rows)) => missing_rows,
+            (None, None) => usize::MAX,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        let BlockSize {
+            min_rows,
+            min_bytes,
+        } = self.size;
+        (min_rows == 0 && min_bytes == 0)
+            || (min_rows > 0 && self.buffered_rows >= min_rows)
+            || (min_bytes > 0 && self.buffered_bytes >= min_bytes)
+    }
+
+    fn flush(&mut self) -> Result<CoalescedBlock> {
+        let buffered = std::mem::take(&mut self.buffered);
+        let (first_batch, last_batch) = self.buffered_batches.take().unwrap_or_default();
+        self.buffered_rows = 0;
+        self.buffered_bytes = 0;
+
+        let block = match <[RecordBatch; 1]>::try_from(buffered) {
+            Ok([block]) => block,
+            Err(buffered) => concat_batches(&self.schema, &buffered)?,
+        };
+        Ok(CoalescedBlock {
+            block,
+            batches: first_batch..=last_batch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Array, Int64Array};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]))
+    }
+
+    fn batch(start: i64, rows: i64) -> RecordBatch {
+        RecordBatch::try_new(
+            schema(),
+            vec![Arc::new(Int64Array::from_iter_values(start..start + rows))],
+        )
+        .unwrap()
+    }
+
+    /// Pushes batches of `sizes` rows, returning every block.
+    fn coalesce_blocks(size: BlockSize, sizes: &[i64]) -> Vec<CoalescedBlock> {
+        let mut coalescer = BlockCoalescer::new(schema(), size);
+        let mut blocks = vec![];
+        let mut start = 0;
+        for rows in sizes {
+            blocks.extend(coalescer.push(batch(start, *rows)).unwrap());
+            start += rows;
+        }
+        blocks.extend(coalescer.finish().unwrap());
+        blocks
+    }
+
+    /// Pushes batches of `sizes` rows, returning the rows of every block and their values in order.
+    fn coalesce(size: BlockSize, sizes: &[i64]) -> (Vec<usize>, Vec<i64>) {
+        let blocks = coalesce_blocks(size, sizes);
+        let values = blocks
+            .iter()
+            .flat_map(|block| {
+                let column = block
+                    .block
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                column.values().to_vec()
+            })
+            .collect();
+        (
+            blocks.iter().map(|block| block.block.num_rows()).collect(),
+            values,
+        )
+    }
+
+    #[test]
+    fn passes_batches_through_without_limits() {
+        let (rows, values) = coalesce(BlockSize::default(), &[300, 0, 2500, 10]);
+        assert_eq!(rows, [300, 2500, 10]);
+        assert_eq!(values, (0..2810).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn concatenates_and_splits_by_rows() {
+        let size = BlockSize {
+            min_rows: 1000,
+            min_bytes: 0,
+        };
+        let (rows, values) = coalesce(size, &[300, 300, 0, 2500, 10, 1000]);
+        assert_eq!(rows, [1000, 1000, 1000, 1000, 110]);
+        assert_eq!(values, (0..4110).collect::<Vec<_>>());
+
+        let (rows, _) = coalesce(size, &[1000, 1000]);
+        assert_eq!(rows, [1000, 1000]);
+    }
+
+    #[test]
+    fn concatenates_and_splits_by_bytes() {
+        let bytes_per_row = batch(0, 1000).get_array_memory_size().div_ceil(1000);
+        let size = BlockSize {
+            min_rows: 0,
+            min_bytes: 500 * bytes_per_row,
+        };
+        let (rows, values) = coalesce(size, &[1000, 200, 200, 200]);
+        assert!(rows.len() >= 3, "{rows:?}");
+        assert!(
+            rows[..rows.len() - 1]
+                .iter()
+                .all(|rows| (400..=600).contains(rows)),
+            "{rows:?}"
+        );
+        assert_eq!(values, (0..1600).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn completes_blocks_at_the_first_limit() {
+        let size = BlockSize {
+            min_rows: 100,
+            min_bytes: usize::MAX,
+        };
+        let (rows, _) = coalesce(size, &[250]);
+        assert_eq!(rows, [100, 100, 50]);
+    }
+
+    #[test]
+    fn estimates_the_bytes_of_slices() {
+        let large = batch(0, 10_000);
+        let total = large.get_array_memory_size();
+        let size = BlockSize {
+            min_rows: 0,
+            min_bytes: 100 * total.div_ceil(10_000),
+        };
+
+        let mut coalescer = BlockCoalescer::new(schema(), size);
+        let blocks = coalescer.push(large).unwrap();
+        assert_eq!(blocks.len(), 100);
+        assert_eq!(blocks[0].block.get_array_memory_size(), total);
+        assert!(blocks.iter().all(|block| block.block.num_rows() == 100));
+    }
+
+    #[test]
+    fn numbers_blocks_by_the_bound_batches_they_come_from() {
+        let batches = |size, sizes| {
+            coalesce_blocks(size, sizes)
+                .into_iter()
+                .map(|block| block.batches)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            batches(BlockSize::default(), &[0, 300, 0, 0, 2500]),
+            [1..=1, 4..=4]
+        );
+
+        let size = BlockSize {
+            min_rows: 1000,
+            min_bytes: 0,
+        };
+        assert_eq!(
+            batches(size, &[300, 0, 300, 2500, 0, 10]),
+            [0..=3, 3..=3, 3..=3, 3..=5]
+        );
+    }
+}
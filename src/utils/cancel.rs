@@ -0,0 +1,285 @@
+use std::{
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use adbc_core::error::{Error, Result, Status};
+use clickhouse_arrow::{ArrowClient, Qid};
+use futures::future::{Either, select};
+use tokio::{sync::watch, time::Instant};
+
+use crate::utils::{ClientFactory, QueryDetails, Runtime, from_clickhouse_error, with_deadline};
+
+/// How long `cancel` waits for the side connection to open and the server to take `KILL QUERY`.
+const KILL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cancellation state of a statement, shared with its connection so both `cancel`s can reach a
+/// query that is blocked on another thread.
+pub(crate) struct CancelState {
+    /// Bumped by every `cancel`, queries started before that return `Status::Cancelled`.
+    generation: watch::Sender<u64>,
+    /// The running query, followed by the queries started alongside it.
+    running: Mutex<Vec<Qid>>,
+    /// The statement's own client, which stops the running query with the native Cancel packet.
+    client: Option<ArrowClient>,
+    /// Opens the side connection used to send `KILL QUERY` for the queries started alongside the
+    /// running one, which run on connections of their own.
+    factory: Option<ClientFactory>,
+}
+
+impl CancelState {
+    pub fn new(client: Option<ArrowClient>, factory: Option<ClientFactory>) -> Self {
+        Self {
+            generation: watch::Sender::new(0),
+            running: Mutex::new(vec![]),
+            client,
+            factory,
+        }
+    }
+
+    /// Registers a new query, which runs until `deadline` or the next [`CancelState::cancel`].
+    pub fn start(self: &Arc<Self>, deadline: Option<Instant>, details: QueryDetails) -> Interrupt {
+        let qid = Qid::new();
+        *self.running.lock().unwrap() = vec![qid];
+
+        Interrupt {
+            deadline,
+            details: QueryDetails {
+                query_id: Some(qid.to_string()),
+                ..details
+            },
+            query: Some(RunningQuery {
+                state: self.clone(),
+                qid,
+                cancelled: self.generation.subscribe(),
+            }),
+        }
+    }
+
+    /// Interrupts the running query, if any, and asks the server to stop it and the queries
+    /// started alongside it.
+    pub fn cancel(&self, rt: &Runtime) -> Result<()> {
+        self.generation.send_modify(|generation| *generation += 1);
+
+        let mut running = std::mem::take(&mut *self.running.lock().unwrap());
+        if running.is_empty() {
+            return Ok(());
+        }
+
+        let qid = running.remove(0);
+        if let Some(client) = &self.client {
+            rt.block_on(client.cancel_query(qid)).map_err(|err| {
+                from_clickhouse_error(format!("Failed to cancel query {qid}"), err)
+            })?;
+        }
+
+        match &self.factory {
+            Some(factory) if !running.is_empty() => rt.block_on(kill_queries(factory, &running)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Runs `KILL QUERY` for `qids` over a side connection of `factory`, giving up after
+/// [`KILL_TIMEOUT`].
+async fn kill_queries(factory: &ClientFactory, qids: &[Qid]) -> Result<()> {
+    let qids = qids.iter().map(Qid::to_string).collect::<Vec<_>>();
+    let context = format!("Failed to kill query {}", qids.join(", "));
+
+    let kill = async {
+        let client = factory.build_arrow().await?;
+        let query = format!(
+            "KILL QUERY WHERE query_id IN ('{}') ASYNC",
+            qids.join("', '")
+        );
+        client.execute(query, None).await
+    };
+    let kill = async {
+        kill.await
+            .map_err(|err| from_clickhouse_error(&context, err))
+    };
+
+    with_deadline(Some(Instant::now() + KILL_TIMEOUT), kill)
+        .await
+        .map_err(|err| match err.status {
+            Status::Timeout => Error::with_message_and_status(
+                format!(
+                    "[Clickhouse] {context}: timed out after {}s",
+                    KILL_TIMEOUT.as_secs()
+                ),
+                Status::Timeout,
+            ),
+            _ => err,
+        })
+}
+
+struct RunningQuery {
+    state: Arc<CancelState>,
+    qid: Qid,
+    cancelled: watch::Receiver<u64>,
+}
+
+/// Bounds everything a single query waits on, from sending it to reading its last batch.
+#[derive(Default)]
+pub(crate) struct Interrupt {
+    deadline: Option<Instant>,
+    /// Attached to every error returned by [`Interrupt::run`].
+    details: QueryDetails,
+    query: Option<RunningQuery>,
+}
+
+impl Interrupt {
+    /// The query id to send the query with, so it can be killed.
+    pub fn qid(&self) -> Option<Qid> {
+        self.query.as_ref().map(|query| query.qid)
+    }
+
+    /// A query id for another query run alongside this one, which is killed with it.
+    pub fn add_qid(&self) -> Qid {
+        let qid = Qid::new();
+        if let Some(query) = &self.query {
+            let mut running = query.state.running.lock().unwrap();
+            if running.first() == Some(&query.qid) {
+                running.push(qid);
+            }
+        }
+        qid
+    }
+
+    /// Bounds a query run on behalf of this one by the same deadline and cancellation. It gets a
+    /// query id of its own but is not registered as the running query, which stays this one.
+    pub fn follow_up(&self, details: QueryDetails) -> Interrupt {
+        let query = self.query.as_ref().map(|query| RunningQuery {
+            state: query.state.clone(),
+            qid: Qid::new(),
+            cancelled: query.cancelled.clone(),
+        });
+
+        Interrupt {
+            deadline: self.deadline,
+            details: QueryDetails {
+                query_id: query.as_ref().map(|query| query.qid.to_string()),
+                ..details
+            },
+            query,
+        }
+    }
+
+    /// Waits for `fut`, failing with `Status::Timeout` past the deadline or `Status::Cancelled`
+    /// once the query is cancelled.
+    pub async fn run<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        self.run_inner(fut)
+            .await
+            .map_err(|err| self.details.attach(err))
+    }
+
+    async fn run_inner<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        let Some(query) = &self.query else {
+            return with_deadline(self.deadline, fut).await;
+        };
+
+        if query.cancelled.has_changed().unwrap_or(false) {
+            return Err(cancelled_error());
+        }
+
+        let mut cancelled = query.cancelled.clone();
+        let cancelled = pin!(async move {
+            if cancelled.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        });
+
+        match select(pin!(with_deadline(self.deadline, fut)), cancelled).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(cancelled_error()),
+        }
+    }
+}
+
+fn cancelled_error() -> Error {
+    Error::with_message_and_status("[Clickhouse] Query was cancelled", Status::Cancelled)
+}
+
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        if let Some(query) = &self.query {
+            let mut running = query.state.running.lock().unwrap();
+            if running.first() == Some(&query.qid) {
+                running.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime() -> Runtime {
+        Runtime::new(Some(1)).unwrap()
+    }
+
+    #[test]
+    fn cancels_the_queries_started_before() {
+        let rt = runtime();
+        let state = Arc::new(CancelState::new(None, None));
+        let before = state.start(None, QueryDetails::default());
+
+        state.cancel(&rt).unwrap();
+        assert_eq!(*state.generation.borrow(), 1);
+
+        let err = rt.block_on(before.run(async { Ok(()) })).unwrap_err();
+        assert_eq!(err.status, Status::Cancelled, "{err:?}");
+        let after = state.start(None, QueryDetails::default());
+        rt.block_on(after.run(async { Ok(()) })).unwrap();
+    }
+
+    #[test]
+    fn interrupts_a_waiting_query() {
+        let rt = runtime();
+        let state = Arc::new(CancelState::new(None, None));
+        let interrupt = state.start(None, QueryDetails::default());
+
+        let canceller = std::thread::spawn({
+            let state = state.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(100));
+                state.cancel(&runtime()).unwrap();
+            }
+        });
+        let err = rt
+            .block_on(interrupt.run(std::future::pending::<Result<()>>()))
+            .unwrap_err();
+        canceller.join().unwrap();
+
+        assert_eq!(err.status, Status::Cancelled, "{err:?}");
+        assert_eq!(
+            err.details.unwrap()[0],
+            (
+                "clickhouse.query_id".to_string(),
+                interrupt.qid().unwrap().to_string().into_bytes()
+            )
+        );
+    }
+
+    #[test]
+    fn registers_the_queries_added_to_the_running_one() {
+        let state = Arc::new(CancelState::new(None, None));
+        let interrupt = state.start(None, QueryDetails::default());
+        let added = interrupt.add_qid();
+        assert_eq!(
+            *state.running.lock().unwrap(),
+            [interrupt.qid().unwrap(), added]
+        );
+
+        // A follow-up is not the running query, so what it adds is not killed with it.
+        let follow_up = interrupt.follow_up(QueryDetails::default());
+        follow_up.add_qid();
+        drop(follow_up);
+        assert_eq!(state.running.lock().unwrap().len(), 2);
+
+        drop(interrupt);
+        assert!(state.running.lock().unwrap().is_empty());
+    }
+}
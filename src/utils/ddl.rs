@@ -0,0 +1,391 @@
+use adbc_core::error::{Error, Result, Status};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+
+use crate::{
+    consts::{
+        STATEMENT_OPTION_INGEST_ENGINE, STATEMENT_OPTION_INGEST_ORDER_BY,
+        STATEMENT_OPTION_INGEST_PARTITION_BY, STATEMENT_OPTION_INGEST_TTL,
+    },
+    utils::quote_identifier,
+};
+
+const DEFAULT_ENGINE: &str = "MergeTree";
+/// Temporary tables of older servers only support the `Memory` engine family.
+const DEFAULT_TEMPORARY_ENGINE: &str = "Memory";
+
+/// Clauses of the tables created by bulk ingest, from the `clickhouse.ingest.*` statement options.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TableOptions {
+    /// Create a `TEMPORARY TABLE`, which lives as long as the server session.
+    pub temporary: bool,
+    /// `MergeTree` when not set, or `Memory` for temporary tables.
+    pub engine: Option<String>,
+    /// `tuple()` when not set and the engine is of the `MergeTree` family.
+    pub order_by: Option<String>,
+    pub partition_by: Option<String>,
+    pub ttl: Option<String>,
+}
+
+impl TableOptions {
+    /// The clause set by the option `key`, `None` for other options.
+    pub fn clause(&self, key: &str) -> Option<&Option<String>> {
+        match key {
+            STATEMENT_OPTION_INGEST_ENGINE => Some(&self.engine),
+            STATEMENT_OPTION_INGEST_ORDER_BY => Some(&self.order_by),
+            STATEMENT_OPTION_INGEST_PARTITION_BY => Some(&self.partition_by),
+            STATEMENT_OPTION_INGEST_TTL => Some(&self.ttl),
+            _ => None,
+        }
+    }
+
+    pub fn clause_mut(&mut self, key: &str) -> Option<&mut Option<String>> {
+        match key {
+            STATEMENT_OPTION_INGEST_ENGINE => Some(&mut self.engine),
+            STATEMENT_OPTION_INGEST_ORDER_BY => Some(&mut self.order_by),
+            STATEMENT_OPTION_INGEST_PARTITION_BY => Some(&mut self.partition_by),
+            STATEMENT_OPTION_INGEST_TTL => Some(&mut self.ttl),
+            _ => None,
+        }
+    }
+
+    fn clauses(&self) -> String {
+        let default_engine = if self.temporary {
+            DEFAULT_TEMPORARY_ENGINE
+        } else {
+            DEFAULT_ENGINE
+        };
+        let engine = self.engine.as_deref().unwrap_or(default_engine);
+        let mut clauses = format!("ENGINE = {engine}");
+
+        if let Some(partition_by) = &self.partition_by {
+            clauses.push_str(&format!(" PARTITION BY {partition_by}"));
+        }
+
+        let engine_name = engine.split('(').next().unwrap_or_default().trim();
+        match &self.order_by {
+            Some(order_by) => clauses.push_str(&format!(" ORDER BY {order_by}")),
+            None if engine_name.ends_with("MergeTree") => clauses.push_str(" ORDER BY tuple()"),
+            None => {}
+        }
+
+        if let Some(ttl) = &self.ttl {
+            clauses.push_str(&format!(" TTL {ttl}"));
+        }
+
+        clauses
+    }
+}
+
+/// Builds the `CREATE TABLE` statement for a table holding batches of `schema`, `table` being
+/// already quoted.
+pub(crate) fn create_table_query(
+    table: &str,
+    schema: &Schema,
+    if_not_exists: bool,
+    options: &TableOptions,
+) -> Result<String> {
+    if schema.fields().is_empty() {
+        return Err(Error::with_message_and_status(
+            "[Clickhouse] Cannot create a table without columns",
+            Status::InvalidArguments,
+        ));
+    }
+
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let column_type = column_type(field).map_err(|err| {
+                Error::with_message_and_status(
+                    format!(
+                        "[Clickhouse] Cannot create column {}: {}",
+                        field.name(),
+                        err.message.trim_start_matches("[Clickhouse] ")
+                    ),
+                    err.status,
+                )
+            })?;
+            Ok(format!("{} {column_type}", quote_identifier(field.name())))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let temporary = if options.temporary { " TEMPORARY" } else { "" };
+    let if_not_exists = if if_not_exists { " IF NOT EXISTS" } else { "" };
+
+    Ok(format!(
+        "CREATE{temporary} TABLE{if_not_exists} {table} ({}) {}",
+        columns.join(", "),
+        options.clauses()
+    ))
+}
+
+/// The ClickHouse type of the column `field` is stored in. Arrays, tuples and maps cannot be
+/// `Nullable` in ClickHouse, they are stored as is whatever the field nullability.
+fn column_type(field: &Field) -> Result<String> {
+    let data_type = match field.data_type() {
+        DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+            return Ok(format!("Array({})", column_type(item)?));
+        }
+        DataType::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    Ok(format!(
+                        "{} {}",
+                        quote_identifier(field.name()),
+                        column_type(field)?
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(format!("Tuple({})", fields.join(", ")));
+        }
+        DataType::Map(entries, _) => {
+            let DataType::Struct(fields) = entries.data_type() else {
+                return Err(unsupported_type(field.data_type()));
+            };
+            let [key, value] = fields.iter().collect::<Vec<_>>()[..] else {
+                return Err(unsupported_type(field.data_type()));
+            };
+            return Ok(format!(
+                "Map({}, {})",
+                scalar_type(key.data_type())?,
+                column_type(value)?
+            ));
+        }
+        DataType::Dictionary(_, value_type) => {
+            let value_type = scalar_type(value_type)?;
+            return Ok(if field.is_nullable() {
+                format!("LowCardinality(Nullable({value_type}))")
+            } else {
+                format!("LowCardinality({value_type})")
+            });
+        }
+        data_type => scalar_type(data_type)?,
+    };
+
+    Ok(if field.is_nullable() {
+        format!("Nullable({data_type})")
+    } else {
+        data_type
+    })
+}
+
+fn scalar_type(data_type: &DataType) -> Result<String> {
+    let scalar_type = match data_type {
+        DataType::Boolean => "Bool".to_string(),
+        DataType::Int8 => "Int8".to_string(),
+        DataType::Int16 => "Int16".to_string(),
+        DataType::Int32 => "Int32".to_string(),
+        DataType::Int64 => "Int64".to_string(),
+        DataType::UInt8 => "UInt8".to_string(),
+        DataType::UInt16 => "UInt16".to_string(),
+        DataType::UInt32 => "UInt32".to_string(),
+        DataType::UInt64 => "UInt64".to_string(),
+        DataType::Float16 | DataType::Float32 => "Float32".to_string(),
+        DataType::Float64 => "Float64".to_string(),
+        DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Utf8View
+        | DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView => "String".to_string(),
+        DataType::FixedSizeBinary(size) => format!("FixedString({size})"),
+        DataType::Date32 => "Date32".to_string(),
+        DataType::Date64 => "DateTime64(3)".to_string(),
+        DataType::Timestamp(unit, None) => format!("DateTime64({})", precision(unit)),
+        DataType::Timestamp(unit, Some(tz)) => format!(
+            "DateTime64({}, '{}')",
+            precision(unit),
+            tz.replace('\\', "\\\\").replace('\'', "\\'")
+        ),
+        DataType::Decimal32(precision, scale)
+        | DataType::Decimal64(precision, scale)
+        | DataType::Decimal128(precision, scale)
+        | DataType::Decimal256(precision, scale)
+            if *scale >= 0 =>
+        {
+            format!("Decimal({precision}, {scale})")
+        }
+        data_type => return Err(unsupported_type(data_type)),
+    };
+
+    Ok(scalar_type)
+}
+
+fn precision(unit: &TimeUnit) -> u8 {
+    match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 3,
+        TimeUnit::Microsecond => 6,
+        TimeUnit::Nanosecond => 9,
+    }
+}
+
+fn unsupported_type(data_type: &DataType) -> Error {
+    Error::with_message_and_status(
+        format!("[Clickhouse] No ClickHouse type for {data_type}"),
+        Status::NotImplemented,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_schema::Fields;
+
+    use super::*;
+
+    fn create(schema: &Schema, options: &TableOptions) -> String {
+        create_table_query("`t`", schema, false, options).unwrap()
+    }
+
+    #[test]
+    fn creates_merge_tree_tables() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+
+        assert_eq!(
+            create(&schema, &TableOptions::default()),
+            "CREATE TABLE `t` (`id` Int64, `name` Nullable(String)) ENGINE = MergeTree ORDER BY tuple()"
+        );
+        assert_eq!(
+            create_table_query("`db`.`t`", &schema, true, &TableOptions::default()).unwrap(),
+            "CREATE TABLE IF NOT EXISTS `db`.`t` (`id` Int64, `name` Nullable(String)) ENGINE = MergeTree ORDER BY tuple()"
+        );
+    }
+
+    #[test]
+    fn applies_table_options() {
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+
+        let temporary = TableOptions {
+            temporary: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            create(&schema, &temporary),
+            "CREATE TEMPORARY TABLE `t` (`id` Int64) ENGINE = Memory"
+        );
+
+        let clauses = TableOptions {
+            engine: Some("ReplacingMergeTree(version)".to_string()),
+            order_by: Some("id".to_string()),
+            partition_by: Some("id % 10".to_string()),
+            ttl: Some("toDate(id) + INTERVAL 1 DAY".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            create(&schema, &clauses),
+            "CREATE TABLE `t` (`id` Int64) ENGINE = ReplacingMergeTree(version) PARTITION BY id % 10 ORDER BY id TTL toDate(id) + INTERVAL 1 DAY"
+        );
+
+        let log = TableOptions {
+            engine: Some("Log".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            create(&schema, &log),
+            "CREATE TABLE `t` (`id` Int64) ENGINE = Log"
+        );
+    }
+
+    #[test]
+    fn quotes_column_names() {
+        let schema = Schema::new(vec![Field::new("a`b; DROP", DataType::Int8, false)]);
+        assert_eq!(
+            create(&schema, &TableOptions::default()),
+            "CREATE TABLE `t` (`a\\`b; DROP` Int8) ENGINE = MergeTree ORDER BY tuple()"
+        );
+    }
+
+    #[test]
+    fn maps_scalar_types() {
+        for (data_type, expected) in [
+            (DataType::Boolean, "Bool"),
+            (DataType::UInt64, "UInt64"),
+            (DataType::Float16, "Float32"),
+            (DataType::LargeUtf8, "String"),
+            (DataType::Binary, "String"),
+            (DataType::FixedSizeBinary(16), "FixedString(16)"),
+            (DataType::Date32, "Date32"),
+            (DataType::Date64, "DateTime64(3)"),
+            (
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+                "DateTime64(6)",
+            ),
+            (
+                DataType::Timestamp(TimeUnit::Second, Some("Europe/O'Town".into())),
+                "DateTime64(0, 'Europe/O\\'Town')",
+            ),
+            (DataType::Decimal128(38, 10), "Decimal(38, 10)"),
+        ] {
+            let schema = Schema::new(vec![Field::new("c", data_type, false)]);
+            assert_eq!(
+                create(&schema, &TableOptions::default()),
+                format!("CREATE TABLE `t` (`c` {expected}) ENGINE = MergeTree ORDER BY tuple()")
+            );
+        }
+    }
+
+    #[test]
+    fn maps_nested_types() {
+        let item = Arc::new(Field::new("item", DataType::Int32, true));
+        let entries = Field::new(
+            "entries",
+            DataType::Struct(Fields::from(vec![
+                Field::new("key", DataType::Utf8, false),
+                Field::new("value", DataType::Float64, true),
+            ])),
+            false,
+        );
+        let schema = Schema::new(vec![
+            Field::new("list", DataType::List(item), true),
+            Field::new(
+                "tuple",
+                DataType::Struct(Fields::from(vec![
+                    Field::new("a", DataType::Int8, false),
+                    Field::new("b c", DataType::Utf8, true),
+                ])),
+                true,
+            ),
+            Field::new("map", DataType::Map(Arc::new(entries), false), false),
+            Field::new(
+                "dict",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                true,
+            ),
+        ]);
+
+        assert_eq!(
+            create(&schema, &TableOptions::default()),
+            "CREATE TABLE `t` (`list` Array(Nullable(Int32)), \
+             `tuple` Tuple(`a` Int8, `b c` Nullable(String)), \
+             `map` Map(String, Nullable(Float64)), \
+             `dict` LowCardinality(Nullable(String))) ENGINE = MergeTree ORDER BY tuple()"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_schemas() {
+        assert_eq!(
+            create_table_query("`t`", &Schema::empty(), false, &TableOptions::default())
+                .unwrap_err()
+                .status,
+            Status::InvalidArguments
+        );
+
+        let schema = Schema::new(vec![Field::new(
+            "d",
+            DataType::Duration(TimeUnit::Second),
+            false,
+        )]);
+        let err = create_table_query("`t`", &schema, false, &TableOptions::default()).unwrap_err();
+        assert_eq!(err.status, Status::NotImplemented);
+        assert!(
+            err.message
+                .starts_with("[Clickhouse] Cannot create column d: ")
+        );
+    }
+}
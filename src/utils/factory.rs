@@ -0,0 +1,27 @@
+use clickhouse_arrow::{ArrowClient, ClientBuilder, NativeClient, SettingValue};
+
+/// Opens the clients of a connection: the arrow client, the lazily opened native client and the
+/// side connections of cancellation and parallel ingest, all with the same options.
+#[derive(Clone)]
+pub(crate) struct ClientFactory {
+    builder: ClientBuilder,
+}
+
+impl ClientFactory {
+    pub fn new(builder: ClientBuilder) -> Self {
+        Self { builder }
+    }
+
+    pub fn with_setting(mut self, name: impl Into<String>, value: impl Into<SettingValue>) -> Self {
+        self.builder = self.builder.with_setting(name, value);
+        self
+    }
+
+    pub async fn build_arrow(&self) -> clickhouse_arrow::Result<ArrowClient> {
+        self.builder.clone().build().await
+    }
+
+    pub async fn build_native(&self) -> clickhouse_arrow::Result<NativeClient> {
+        self.builder.clone().build().await
+    }
+}
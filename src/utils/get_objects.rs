@@ -1,11 +1,14 @@
 use adbc_core::{error::Result, options::ObjectDepth, schemas};
 use arrow_array::*;
-use clickhouse_arrow::NativeClient;
+use clickhouse_arrow::{NativeClient, QueryParams, Row};
 use itertools::Itertools;
 
 use crate::{Catalog, ColumnSchema, DbSchema, TableSchema, utils::from_clickhouse_error};
 
-use super::{ClickhouseResponseExt, NativeClientExt};
+use super::{
+    ClickhouseResponseExt, ColumnRow, QueryDetails, SchemaRow, TableRow, columns_query,
+    min_schemas_query, min_tables_query,
+};
 
 pub(crate) struct GetObjectsBuilder<'a> {
     catalog_filter: Option<&'a str>,
@@ -13,6 +16,7 @@ pub(crate) struct GetObjectsBuilder<'a> {
     table_filter: Option<&'a str>,
     table_type_filter: Option<Vec<&'a str>>,
     column_filter: Option<&'a str>,
+    endpoint: Option<&'a str>,
 }
 
 impl<'a> GetObjectsBuilder<'a> {
@@ -29,9 +33,16 @@ impl<'a> GetObjectsBuilder<'a> {
             table_filter,
             table_type_filter,
             column_filter,
+            endpoint: None,
         }
     }
 
+    /// Host reported in the `clickhouse.endpoint` detail of the errors.
+    pub fn with_endpoint(mut self, endpoint: Option<&'a str>) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
     pub async fn build(
         self,
         native_client: &NativeClient,
@@ -57,22 +68,38 @@ impl<'a> GetObjectsBuilder<'a> {
         Ok(record_batch)
     }
 
+    /// Runs one of the metadata queries, attaching it and the endpoint to its errors.
+    async fn fetch<T: Row + Send + 'static>(
+        &self,
+        native_client: &NativeClient,
+        (sql, params): (String, Option<QueryParams>),
+        what: &str,
+    ) -> Result<Vec<T>> {
+        let details = QueryDetails::new(&sql, self.endpoint);
+        let rows = async {
+            native_client
+                .query_params::<T>(sql, params, None)
+                .await
+                .map_err(|err| from_clickhouse_error(format!("Failed to fetch {what}"), err))?
+                .collect_all()
+                .await
+                .map_err(|err| from_clickhouse_error(format!("Failed to parse {what}"), err))
+        };
+
+        rows.await.map_err(|err| details.attach(err))
+    }
+
     async fn fetch_all(&self, native_client: &NativeClient) -> Result<Vec<Catalog>> {
-        let columns = native_client
-            .fetch_all(
-                self.catalog_filter.map(|v| v.to_string()),
-                self.schema_filter.map(|v| v.to_string()),
-                self.table_filter.map(|v| v.to_string()),
-                self.table_type_filter
-                    .as_ref()
-                    .map(|v| v.iter().map(|v| v.to_string()).collect()),
-                self.column_filter.map(|v| v.to_string()),
-            )
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to fetch tables", err))?
-            .collect_all()
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to parse tables", err))?;
+        let query = columns_query(
+            self.catalog_filter.map(|v| v.to_string()),
+            self.schema_filter.map(|v| v.to_string()),
+            self.table_filter.map(|v| v.to_string()),
+            self.table_type_filter
+                .as_ref()
+                .map(|v| v.iter().map(|v| v.to_string()).collect()),
+            self.column_filter.map(|v| v.to_string()),
+        );
+        let columns: Vec<ColumnRow> = self.fetch(native_client, query, "tables").await?;
 
         let catalogs = columns
             .into_iter()
@@ -159,20 +186,15 @@ impl<'a> GetObjectsBuilder<'a> {
     }
 
     async fn fetch_min_tables(&self, native_client: &NativeClient) -> Result<Vec<Catalog>> {
-        let tables = native_client
-            .fetch_min_schema_tables(
-                self.catalog_filter.map(|v| v.to_string()),
-                self.schema_filter.map(|v| v.to_string()),
-                self.table_filter.map(|v| v.to_string()),
-                self.table_type_filter
-                    .as_ref()
-                    .map(|v| v.iter().map(|v| v.to_string()).collect()),
-            )
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to fetch tables", err))?
-            .collect_all()
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to parse tables", err))?;
+        let query = min_tables_query(
+            self.catalog_filter.map(|v| v.to_string()),
+            self.schema_filter.map(|v| v.to_string()),
+            self.table_filter.map(|v| v.to_string()),
+            self.table_type_filter
+                .as_ref()
+                .map(|v| v.iter().map(|v| v.to_string()).collect()),
+        );
+        let tables: Vec<TableRow> = self.fetch(native_client, query, "tables").await?;
 
         let catalogs = tables
             .into_iter()
@@ -210,16 +232,11 @@ impl<'a> GetObjectsBuilder<'a> {
     }
 
     async fn fetch_min_schemas(&self, native_client: &NativeClient) -> Result<Vec<Catalog>> {
-        let schemas = native_client
-            .fetch_min_schemas(
-                self.catalog_filter.map(|v| v.to_string()),
-                self.schema_filter.map(|v| v.to_string()),
-            )
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to fetch schemas", err))?
-            .collect_all()
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to parse schemas", err))?;
+        let query = min_schemas_query(
+            self.catalog_filter.map(|v| v.to_string()),
+            self.schema_filter.map(|v| v.to_string()),
+        );
+        let schemas: Vec<SchemaRow> = self.fetch(native_client, query, "schemas").await?;
 
         let catalogs = schemas
             .into_iter()
@@ -243,13 +260,8 @@ impl<'a> GetObjectsBuilder<'a> {
     }
 
     async fn fetch_min_catalogs(&self, native_client: &NativeClient) -> Result<Vec<Catalog>> {
-        let catalogs = native_client
-            .fetch_min_schemas(self.catalog_filter.map(|v| v.to_string()), None)
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to fetch catalogs", err))?
-            .collect_all()
-            .await
-            .map_err(|err| from_clickhouse_error("Failed to parse catalogs", err))?;
+        let query = min_schemas_query(self.catalog_filter.map(|v| v.to_string()), None);
+        let catalogs: Vec<SchemaRow> = self.fetch(native_client, query, "catalogs").await?;
 
         let catalogs = catalogs
             .into_iter()
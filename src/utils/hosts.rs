@@ -0,0 +1,122 @@
+use std::{
+    fmt,
+    hash::{BuildHasher, RandomState},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use adbc_core::error::{Error, Result, Status};
+
+/// How `new_connection` picks one of several hosts listed in the uri. Whatever host is tried
+/// first, the remaining ones are tried in turn when it cannot be reached.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LoadBalancing {
+    /// Always start with the first host, only moving on when it fails.
+    #[default]
+    InOrder,
+    /// Start with a random host.
+    Random,
+    /// Start with the host after the one the previous connection started with.
+    RoundRobin,
+}
+
+impl LoadBalancing {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "in_order" => Ok(Self::InOrder),
+            "random" => Ok(Self::Random),
+            "round_robin" => Ok(Self::RoundRobin),
+            _ => Err(Error::with_message_and_status(
+                format!(
+                    "[Clickhouse] Invalid load balancing strategy: {value}, expected in_order, random or round_robin"
+                ),
+                Status::InvalidArguments,
+            )),
+        }
+    }
+
+    /// The indices of `hosts` hosts in the order they should be tried.
+    pub fn order(&self, hosts: usize, next: &AtomicUsize) -> Vec<usize> {
+        if hosts == 0 {
+            return vec![];
+        }
+
+        let first = match self {
+            Self::InOrder => 0,
+            Self::Random => {
+                RandomState::new().hash_one(next.fetch_add(1, Ordering::Relaxed)) as usize % hosts
+            }
+            Self::RoundRobin => next.fetch_add(1, Ordering::Relaxed) % hosts,
+        };
+
+        (0..hosts).map(|i| (first + i) % hosts).collect()
+    }
+}
+
+impl fmt::Display for LoadBalancing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InOrder => f.write_str("in_order"),
+            Self::Random => f.write_str("random"),
+            Self::RoundRobin => f.write_str("round_robin"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_strategies() {
+        assert_eq!(
+            LoadBalancing::parse("in_order").unwrap(),
+            LoadBalancing::InOrder
+        );
+        assert_eq!(
+            LoadBalancing::parse("RANDOM").unwrap(),
+            LoadBalancing::Random
+        );
+        assert_eq!(
+            LoadBalancing::parse("round_robin").unwrap(),
+            LoadBalancing::RoundRobin
+        );
+        assert_eq!(
+            LoadBalancing::parse("nearest").unwrap_err().status,
+            Status::InvalidArguments
+        );
+    }
+
+    #[test]
+    fn orders_in_order() {
+        let next = AtomicUsize::new(0);
+        assert_eq!(LoadBalancing::InOrder.order(3, &next), [0, 1, 2]);
+        assert_eq!(LoadBalancing::InOrder.order(3, &next), [0, 1, 2]);
+        assert!(LoadBalancing::InOrder.order(0, &next).is_empty());
+    }
+
+    #[test]
+    fn orders_round_robin() {
+        let next = AtomicUsize::new(0);
+        assert_eq!(LoadBalancing::RoundRobin.order(3, &next), [0, 1, 2]);
+        assert_eq!(LoadBalancing::RoundRobin.order(3, &next), [1, 2, 0]);
+        assert_eq!(LoadBalancing::RoundRobin.order(3, &next), [2, 0, 1]);
+        assert_eq!(LoadBalancing::RoundRobin.order(3, &next), [0, 1, 2]);
+        assert!(LoadBalancing::RoundRobin.order(0, &next).is_empty());
+    }
+
+    #[test]
+    fn orders_random_hosts_once_each() {
+        let next = AtomicUsize::new(0);
+        let mut firsts = [false; 4];
+        for _ in 0..200 {
+            let order = LoadBalancing::Random.order(4, &next);
+            firsts[order[0]] = true;
+
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert_eq!(sorted, [0, 1, 2, 3]);
+            assert!(order.windows(2).all(|w| w[1] == (w[0] + 1) % 4));
+        }
+        assert!(firsts.iter().all(|first| *first));
+    }
+}
@@ -0,0 +1,258 @@
+use std::{fmt, sync::Arc};
+
+use adbc_core::{
+    constants,
+    error::{Error, Result, Status},
+};
+use arrow_array::RecordBatch;
+use clickhouse_arrow::{ArrowClient, ArrowFormat, InsertStream, Qid, Settings};
+use futures::{TryStreamExt, future::try_join_all};
+use tokio::{
+    sync::{Mutex, mpsc},
+    task::JoinSet,
+};
+
+use crate::utils::{ClientFactory, CoalescedBlock, Interrupt, from_clickhouse_error};
+
+/// What bulk ingest does with the target table before inserting, see `adbc.ingest.mode`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IngestMode {
+    /// Create the table, failing with `Status::AlreadyExists` if it exists.
+    #[default]
+    Create,
+    /// Insert into an existing table, failing with `Status::NotFound` if there is none.
+    Append,
+    /// Drop the table if it exists and create it again.
+    Replace,
+    /// Create the table unless it exists, then insert.
+    CreateAppend,
+}
+
+impl IngestMode {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            constants::ADBC_INGEST_OPTION_MODE_CREATE => Ok(Self::Create),
+            constants::ADBC_INGEST_OPTION_MODE_APPEND => Ok(Self::Append),
+            constants::ADBC_INGEST_OPTION_MODE_REPLACE => Ok(Self::Replace),
+            constants::ADBC_INGEST_OPTION_MODE_CREATE_APPEND => Ok(Self::CreateAppend),
+            _ => Err(Error::with_message_and_status(
+                format!("[Clickhouse] Invalid ingest mode: {value}"),
+                Status::InvalidArguments,
+            )),
+        }
+    }
+
+    /// Whether the data goes into a table that may have existed before, whose columns may not
+    /// match the data.
+    pub fn appends(&self) -> bool {
+        matches!(self, Self::Append | Self::CreateAppend)
+    }
+}
+
+impl fmt::Display for IngestMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Create => f.write_str(constants::ADBC_INGEST_OPTION_MODE_CREATE),
+            Self::Append => f.write_str(constants::ADBC_INGEST_OPTION_MODE_APPEND),
+            Self::Replace => f.write_str(constants::ADBC_INGEST_OPTION_MODE_REPLACE),
+            Self::CreateAppend => f.write_str(constants::ADBC_INGEST_OPTION_MODE_CREATE_APPEND),
+        }
+    }
+}
+
+/// Exception codes ClickHouse raises when the inserted columns do not match the table.
+const SCHEMA_MISMATCH_CODES: &[i32] = &[
+    8,  // THERE_IS_NO_COLUMN
+    10, // NOT_FOUND_COLUMN_IN_BLOCK
+    16, // NO_SUCH_COLUMN_IN_TABLE
+    53, // TYPE_MISMATCH
+    70, // CANNOT_CONVERT_TYPE
+];
+
+/// Reports an insert into an existing table whose columns do not match the data with
+/// `Status::AlreadyExists`, as ADBC requires for appends.
+pub(crate) fn schema_mismatch_error(mut error: Error) -> Error {
+    if SCHEMA_MISMATCH_CODES.contains(&error.vendor_code) {
+        error.status = Status::AlreadyExists;
+    }
+    error
+}
+
+/// Opens a single INSERT of `query` and streams the blocks `send_blocks` produces into it as
+/// Native data blocks, then waits until the server has acknowledged the whole insert. The server
+/// squashes the blocks into as few parts as their size allows. The INSERT is cancelled if
+/// `send_blocks` fails or the future is dropped on a timeout or cancel, blocks the server already
+/// received may have been written.
+pub(crate) async fn insert_streamed<T>(
+    client: &ArrowClient,
+    query: &str,
+    qid: Option<Qid>,
+    send_blocks: impl AsyncFnOnce(&mut InsertStream<'_, ArrowFormat>) -> Result<T>,
+) -> Result<T> {
+    let mut insert = client
+        .insert_stream(query, qid)
+        .await
+        .map_err(|err| from_clickhouse_error("Failed to execute update", err))?;
+
+    let result = match send_blocks(&mut insert).await {
+        Ok(result) => result,
+        Err(err) => {
+            // The error of the blocks is the one to report, not how the server took the cancel.
+            let _ = insert.cancel().await;
+            return Err(err);
+        }
+    };
+
+    insert
+        .finish()
+        .await
+        .map_err(|err| from_clickhouse_error("Failed to execute update", err))?
+        .try_collect::<Vec<()>>()
+        .await
+        .map_err(|err| from_clickhouse_error("Failed to execute update", err))?;
+    Ok(result)
+}
+
+/// Sends `block` into the open INSERT, failing with the exception the server ended it with, if
+/// any.
+pub(crate) async fn send_block(
+    insert: &mut InsertStream<'_, ArrowFormat>,
+    block: RecordBatch,
+) -> Result<()> {
+    insert
+        .send(block)
+        .await
+        .map_err(|err| from_clickhouse_error("Failed to execute update", err))
+}
+
+/// A block of a parallel ingest with the index of its first row in the bound data.
+struct IngestBlock {
+    first_row: usize,
+    block: CoalescedBlock,
+}
+
+/// Bulk ingest over several connections of its own, taking blocks off a shared queue. Each
+/// connection streams the blocks it takes into a single INSERT, so the ingest creates about as
+/// few parts per connection as a sequential ingest. The first failure stops the others. Dropping
+/// it aborts the INSERTs in flight, which closes their connections so the server stops them too.
+pub(crate) struct ParallelInsert {
+    sender: mpsc::Sender<IngestBlock>,
+    workers: JoinSet<Result<()>>,
+    next_row: usize,
+}
+
+impl ParallelInsert {
+    /// Opens `parallelism` connections from `factory`, all sending `query` with `settings`. Their
+    /// queries are killed along with the one of `interrupt`.
+    pub async fn start(
+        factory: &ClientFactory,
+        query: &str,
+        settings: Settings,
+        parallelism: usize,
+        interrupt: &Interrupt,
+    ) -> Result<Self> {
+        let clients = try_join_all((0..parallelism).map(|_| factory.build_arrow()))
+            .await
+            .map_err(|err| from_clickhouse_error("Failed to open ingest connection", err))?;
+
+        let (sender, receiver) = mpsc::channel(parallelism);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let query: Arc<str> = query.into();
+        let mut workers = JoinSet::new();
+        for client in clients {
+            workers.spawn(insert_worker(
+                client.with_query_settings(settings.clone()),
+                query.clone(),
+                interrupt.add_qid(),
+                receiver.clone(),
+            ));
+        }
+
+        Ok(Self {
+            sender,
+            workers,
+            next_row: 0,
+        })
+    }
+
+    /// Queues `block`, waiting while every connection is busy.
+    pub async fn send(&mut self, block: CoalescedBlock) -> Result<()> {
+        let ingest_block = IngestBlock {
+            first_row: self.next_row,
+            block,
+        };
+        self.next_row += ingest_block.block.block.num_rows();
+
+        if self.sender.send(ingest_block).await.is_err() {
+            // The queue is only closed by a failing worker.
+            join_workers(&mut self.workers).await?;
+            return Err(Error::with_message_and_status(
+                "[Clickhouse] Ingest connections stopped unexpectedly",
+                Status::Internal,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Waits for the queued blocks to be inserted.
+    pub async fn finish(self) -> Result<()> {
+        let Self {
+            sender,
+            mut workers,
+            ..
+        } = self;
+        drop(sender);
+        join_workers(&mut workers).await
+    }
+}
+
+async fn insert_worker(
+    client: ArrowClient,
+    query: Arc<str>,
+    qid: Qid,
+    receiver: Arc<Mutex<mpsc::Receiver<IngestBlock>>>,
+) -> Result<()> {
+    insert_streamed(&client, &query, Some(qid), async |insert| {
+        loop {
+            let next = receiver.lock().await.recv().await;
+            let Some(ingest_block) = next else {
+                return Ok(());
+            };
+
+            let IngestBlock { first_row, block } = ingest_block;
+            let last_row = first_row + block.block.num_rows();
+            let batches = match block.batches.into_inner() {
+                (first, last) if first == last => format!("batch {first}"),
+                (first, last) => format!("batches {first} to {last}"),
+            };
+            if let Err(err) = send_block(insert, block.block).await {
+                receiver.lock().await.close();
+                return Err(Error {
+                    message: format!(
+                        "[Clickhouse] Failed to insert {batches} (rows {first_row} to {last_row}): {}",
+                        err.message.trim_start_matches("[Clickhouse] ")
+                    ),
+                    ..err
+                });
+            }
+        }
+    })
+    .await
+}
+
+/// Waits for every worker, aborting the others at the first failure.
+async fn join_workers(workers: &mut JoinSet<Result<()>>) -> Result<()> {
+    while let Some(result) = workers.join_next().await {
+        let result = result.unwrap_or_else(|err| {
+            Err(Error::with_message_and_status(
+                format!("[Clickhouse] Ingest connection failed: {err}"),
+                Status::Internal,
+            ))
+        });
+        if result.is_err() {
+            workers.abort_all();
+            return result;
+        }
+    }
+    Ok(())
+}
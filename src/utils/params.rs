@@ -0,0 +1,366 @@
+use adbc_core::error::{Error, Result, Status};
+use arrow_array::{
+    Array, RecordBatch,
+    cast::AsArray,
+    types::{
+        TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+        TimestampSecondType,
+    },
+};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{DataType, TimeUnit};
+use clickhouse_arrow::{ParamValue, QueryParams};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Turns one row of a bound batch into query parameters, each column filling the `{name:Type}`
+/// placeholder of the same name.
+///
+/// ClickHouse receives every parameter as text and parses it as the placeholder type, using the
+/// escaped (TSV) format at the top level and SQL literals inside arrays, tuples and maps.
+pub(crate) fn row_params(batch: &RecordBatch, row: usize) -> Result<QueryParams> {
+    batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, column)| {
+            let value = param_value(column.as_ref(), row).map_err(|err| {
+                Error::with_message_and_status(
+                    format!(
+                        "[Clickhouse] Cannot bind parameter {}: {}",
+                        field.name(),
+                        err.message.trim_start_matches("[Clickhouse] ")
+                    ),
+                    err.status,
+                )
+            })?;
+            Ok((field.name().clone(), value))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(QueryParams)
+}
+
+fn param_value(array: &dyn Array, row: usize) -> Result<ParamValue> {
+    if array.is_valid(row) {
+        match array.data_type() {
+            DataType::Boolean => return Ok(ParamValue::Bool(array.as_boolean().value(row))),
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32 => {
+                if let Ok(value) = text_value(array, row, false)?.parse() {
+                    return Ok(ParamValue::Int(value));
+                }
+            }
+            DataType::Float32 | DataType::Float64 => {
+                if let Ok(value) = text_value(array, row, false)?.parse() {
+                    return Ok(ParamValue::Float(value));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The client only escapes quotes when sending a parameter, backslashes are left to us.
+    Ok(ParamValue::String(
+        text_value(array, row, false)?.replace('\\', "\\\\"),
+    ))
+}
+
+/// Renders a value the way ClickHouse parses it, as escaped text at the top level or as a SQL
+/// literal when `nested` in a composite value.
+fn text_value(array: &dyn Array, row: usize, nested: bool) -> Result<String> {
+    if array.is_null(row) {
+        return Ok(if nested { "NULL" } else { "\\N" }.to_string());
+    }
+
+    let text = match array.data_type() {
+        DataType::Utf8 => string_value(array.as_string::<i32>().value(row), nested),
+        DataType::LargeUtf8 => string_value(array.as_string::<i64>().value(row), nested),
+        DataType::Utf8View => string_value(array.as_string_view().value(row), nested),
+        DataType::Binary => binary_value(array.as_binary::<i32>().value(row), nested)?,
+        DataType::LargeBinary => binary_value(array.as_binary::<i64>().value(row), nested)?,
+        DataType::BinaryView => binary_value(array.as_binary_view().value(row), nested)?,
+        DataType::FixedSizeBinary(_) => {
+            binary_value(array.as_fixed_size_binary().value(row), nested)?
+        }
+        DataType::List(_) => list_value(array.as_list::<i32>().value(row).as_ref())?,
+        DataType::LargeList(_) => list_value(array.as_list::<i64>().value(row).as_ref())?,
+        DataType::FixedSizeList(_, _) => {
+            list_value(array.as_fixed_size_list().value(row).as_ref())?
+        }
+        DataType::Struct(_) => {
+            let values = array
+                .as_struct()
+                .columns()
+                .iter()
+                .map(|column| text_value(column.as_ref(), row, true))
+                .collect::<Result<Vec<_>>>()?;
+            format!("({})", values.join(","))
+        }
+        DataType::Map(_, _) => {
+            let entries = array.as_map().value(row);
+            let values = (0..entries.len())
+                .map(|idx| {
+                    Ok(format!(
+                        "{}:{}",
+                        text_value(entries.column(0).as_ref(), idx, true)?,
+                        text_value(entries.column(1).as_ref(), idx, true)?
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            format!("{{{}}}", values.join(","))
+        }
+        DataType::Dictionary(_, value_type) => {
+            let value = arrow_cast::cast(&array.slice(row, 1), value_type)?;
+            return text_value(value.as_ref(), 0, nested);
+        }
+        DataType::Date32
+        | DataType::Date64
+        | DataType::Timestamp(_, _)
+        | DataType::Time32(_)
+        | DataType::Time64(_) => {
+            let text = match array.data_type() {
+                DataType::Timestamp(unit, Some(_)) => epoch_value(array, row, *unit),
+                _ => format_value(array, row)?,
+            };
+            if nested { format!("'{text}'") } else { text }
+        }
+        DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64
+        | DataType::Float16
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Decimal32(_, _)
+        | DataType::Decimal64(_, _)
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => format_value(array, row)?,
+        data_type => {
+            return Err(Error::with_message_and_status(
+                format!("[Clickhouse] Unsupported parameter type {data_type}"),
+                Status::NotImplemented,
+            ));
+        }
+    };
+
+    Ok(text)
+}
+
+fn list_value(values: &dyn Array) -> Result<String> {
+    let values = (0..values.len())
+        .map(|idx| text_value(values, idx, true))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(format!("[{}]", values.join(",")))
+}
+
+fn string_value(value: &str, nested: bool) -> String {
+    if nested {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+    } else {
+        value
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+    }
+}
+
+fn binary_value(value: &[u8], nested: bool) -> Result<String> {
+    let value = std::str::from_utf8(value).map_err(|_| {
+        Error::with_message_and_status(
+            "[Clickhouse] Binary parameters must be valid UTF-8",
+            Status::InvalidArguments,
+        )
+    })?;
+    Ok(string_value(value, nested))
+}
+
+/// A time zone aware timestamp as seconds since the epoch, which ClickHouse reads as the same
+/// instant whatever the time zone of the placeholder type, unlike a wall-clock time.
+fn epoch_value(array: &dyn Array, row: usize, unit: TimeUnit) -> String {
+    let (value, per_second, digits) = match unit {
+        TimeUnit::Second => (array.as_primitive::<TimestampSecondType>().value(row), 1, 0),
+        TimeUnit::Millisecond => (
+            array.as_primitive::<TimestampMillisecondType>().value(row),
+            1_000,
+            3,
+        ),
+        TimeUnit::Microsecond => (
+            array.as_primitive::<TimestampMicrosecondType>().value(row),
+            1_000_000,
+            6,
+        ),
+        TimeUnit::Nanosecond => (
+            array.as_primitive::<TimestampNanosecondType>().value(row),
+            1_000_000_000,
+            9,
+        ),
+    };
+
+    let sign = if value < 0 { "-" } else { "" };
+    let (seconds, fraction) = (
+        value.unsigned_abs() / per_second,
+        value.unsigned_abs() % per_second,
+    );
+    if fraction == 0 {
+        format!("{sign}{seconds}")
+    } else {
+        format!("{sign}{seconds}.{fraction:0digits$}")
+    }
+}
+
+fn format_value(array: &dyn Array, row: usize) -> Result<String> {
+    let options = FormatOptions::new()
+        .with_date_format(Some(DATE_FORMAT))
+        .with_datetime_format(Some(DATETIME_FORMAT))
+        .with_timestamp_format(Some(DATETIME_FORMAT));
+    Ok(ArrayFormatter::try_new(array, &options)?
+        .value(row)
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        ArrayRef, DictionaryArray, Int32Array, ListArray, MapArray, StringArray, StructArray,
+        TimestampMicrosecondArray, UInt64Array,
+        builder::{Int32Builder, MapBuilder, StringBuilder},
+        types::Int32Type,
+    };
+    use arrow_schema::{Field, Schema};
+
+    use super::*;
+
+    /// The parameters of every row of a batch with a single column `p`.
+    fn params(column: ArrayRef) -> Vec<ParamValue> {
+        let schema = Schema::new(vec![Field::new("p", column.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![column]).unwrap();
+        (0..batch.num_rows())
+            .map(|row| {
+                let QueryParams(mut params) = row_params(&batch, row).unwrap();
+                assert_eq!(params.len(), 1);
+                let (name, value) = params.pop().unwrap();
+                assert_eq!(name, "p");
+                value
+            })
+            .collect()
+    }
+
+    fn string(value: &str) -> ParamValue {
+        ParamValue::String(value.to_string())
+    }
+
+    #[test]
+    fn escapes_top_level_strings() {
+        let column = StringArray::from(vec![Some("a\tb\nc"), Some("back\\slash 'quote'"), None]);
+        // Escaped for TSV, then backslashes doubled once more for the quoted parameter.
+        assert_eq!(
+            params(Arc::new(column)),
+            [
+                string("a\\\\tb\\\\nc"),
+                string("back\\\\\\\\slash 'quote'"),
+                string("\\\\N"),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_numbers() {
+        assert_eq!(
+            params(Arc::new(Int32Array::from(vec![Some(-7), None]))),
+            [ParamValue::Int(-7), string("\\\\N")]
+        );
+        // UInt64 does not fit `ParamValue::Int` and is sent as text.
+        assert_eq!(
+            params(Arc::new(UInt64Array::from(vec![u64::MAX]))),
+            [string("18446744073709551615")]
+        );
+    }
+
+    #[test]
+    fn renders_lists_as_literals() {
+        let column = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), None, Some(3)]),
+            Some(vec![]),
+        ]);
+        assert_eq!(
+            params(Arc::new(column)),
+            [string("[1,NULL,3]"), string("[]")]
+        );
+    }
+
+    #[test]
+    fn renders_structs_as_tuples() {
+        let column = StructArray::from(vec![
+            (
+                Arc::new(Field::new("a", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![Some(1), None])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("b", DataType::Utf8, true)),
+                Arc::new(StringArray::from(vec![Some("it's\t\\"), Some("x")])) as ArrayRef,
+            ),
+        ]);
+        assert_eq!(
+            params(Arc::new(column)),
+            [string("(1,'it\\\\'s\t\\\\\\\\')"), string("(NULL,'x')")]
+        );
+    }
+
+    #[test]
+    fn renders_maps() {
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        builder.keys().append_value("a");
+        builder.values().append_value(1);
+        builder.keys().append_value("b");
+        builder.values().append_null();
+        builder.append(true).unwrap();
+        let column: MapArray = builder.finish();
+        assert_eq!(params(Arc::new(column)), [string("{'a':1,'b':NULL}")]);
+    }
+
+    #[test]
+    fn unpacks_dictionaries() {
+        let column: DictionaryArray<Int32Type> =
+            vec![Some("x\ty"), None, Some("x\ty")].into_iter().collect();
+        assert_eq!(
+            params(Arc::new(column)),
+            [string("x\\\\ty"), string("\\\\N"), string("x\\\\ty")]
+        );
+    }
+
+    #[test]
+    fn formats_time_zone_aware_timestamps_as_epoch_seconds() {
+        let column = TimestampMicrosecondArray::from(vec![1_500_000, -1_500_000, 2_000_000])
+            .with_timezone("+02:00".to_string());
+        assert_eq!(
+            params(Arc::new(column.clone())),
+            [string("1.500000"), string("-1.500000"), string("2")]
+        );
+
+        let nested = ListArray::try_new(
+            Arc::new(Field::new_list_field(column.data_type().clone(), true)),
+            arrow_buffer::OffsetBuffer::from_lengths([3]),
+            Arc::new(column),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            params(Arc::new(nested)),
+            [string("['1.500000','-1.500000','2']")]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    iter::Peekable,
+    ops::Range,
+    str::{CharIndices, FromStr},
+};
+
+use adbc_core::error::{Error, Result, Status};
+use arrow_schema::{Field, Schema};
+use clickhouse_arrow::{ArrowOptions, Type, arrow::ch_to_arrow_type};
+
+use crate::utils::quote_identifier;
+
+/// A `{name:Type}` query parameter placeholder.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Placeholder {
+    pub name: String,
+    pub type_name: String,
+}
+
+/// Finds the `{name:Type}` placeholders of a query in order of first appearance, skipping string
+/// literals, quoted identifiers and comments.
+pub(crate) fn parse_placeholders(query: &str) -> Result<Vec<Placeholder>> {
+    let mut placeholders: Vec<Placeholder> = vec![];
+
+    for (_, placeholder) in scan_placeholders(query) {
+        match placeholders.iter().find(|p| p.name == placeholder.name) {
+            Some(existing) if existing.type_name != placeholder.type_name => {
+                return Err(Error::with_message_and_status(
+                    format!(
+                        "[Clickhouse] Parameter {} is used as both {} and {}",
+                        placeholder.name, existing.type_name, placeholder.type_name
+                    ),
+                    Status::InvalidArguments,
+                ));
+            }
+            Some(_) => {}
+            None => placeholders.push(placeholder),
+        }
+    }
+
+    Ok(placeholders)
+}
+
+/// Whether `query` has `{name:Identifier}` placeholders, which stand for tables or columns only
+/// known once the parameters are bound.
+pub(crate) fn has_identifier_placeholders(query: &str) -> bool {
+    scan_placeholders(query)
+        .iter()
+        .any(|(_, placeholder)| placeholder.type_name == "Identifier")
+}
+
+/// Replaces every placeholder with the default value of its type, or with its name for
+/// `Identifier` placeholders, so the query can be checked without binding parameters. Only
+/// queries without [`has_identifier_placeholders`] can be described that way.
+pub(crate) fn substitute_placeholders(query: &str) -> String {
+    let mut substituted = String::with_capacity(query.len());
+    let mut last = 0;
+
+    for (span, placeholder) in scan_placeholders(query) {
+        substituted.push_str(&query[last..span.start]);
+        if placeholder.type_name == "Identifier" {
+            substituted.push_str(&quote_identifier(&placeholder.name));
+        } else {
+            substituted.push_str(&format!(
+                "defaultValueOfTypeName('{}')",
+                placeholder
+                    .type_name
+                    .replace('\\', "\\\\")
+                    .replace('\'', "\\'")
+            ));
+        }
+        last = span.end;
+    }
+
+    substituted.push_str(&query[last..]);
+    substituted
+}
+
+/// Every placeholder occurrence with the byte range it spans, braces included.
+fn scan_placeholders(query: &str) -> Vec<(Range<usize>, Placeholder)> {
+    let mut placeholders = vec![];
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => skip_quoted(&mut chars, c),
+            '-' if next_is(&mut chars, '-') => skip_line(&mut chars),
+            '#' if chars.peek().is_some_and(|(_, c)| *c == ' ' || *c == '!') => {
+                skip_line(&mut chars)
+            }
+            '/' if next_is(&mut chars, '*') => skip_block_comment(&mut chars),
+            '{' => {
+                let Some(end) = query[start..].find('}') else {
+                    continue;
+                };
+                let Some(placeholder) = placeholder(&query[start + 1..start + end]) else {
+                    continue;
+                };
+                while chars.next_if(|(idx, _)| *idx <= start + end).is_some() {}
+
+                placeholders.push((start..start + end + 1, placeholder));
+            }
+            _ => {}
+        }
+    }
+
+    placeholders
+}
+
+/// The Arrow schema of a batch binding every placeholder of `query`, see
+/// [`crate::utils::row_params`].
+pub(crate) fn parameter_schema(query: &str) -> Result<Schema> {
+    let options = ArrowOptions::default().with_strings_as_strings(true);
+
+    let fields = parse_placeholders(query)?
+        .into_iter()
+        .map(|placeholder| {
+            let (data_type, nullable) = Type::from_str(&placeholder.type_name)
+                .and_then(|ch_type| ch_to_arrow_type(&ch_type, Some(options)))
+                .map_err(|err| {
+                    Error::with_message_and_status(
+                        format!(
+                            "[Clickhouse] Unsupported type {} of parameter {}: {err}",
+                            placeholder.type_name, placeholder.name
+                        ),
+                        Status::InvalidArguments,
+                    )
+                })?;
+            Ok(Field::new(placeholder.name, data_type, nullable))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Schema::new(fields))
+}
+
+fn placeholder(inner: &str) -> Option<Placeholder> {
+    let (name, type_name) = inner.split_once(':')?;
+    let (name, type_name) = (name.trim(), type_name.trim());
+
+    if name.is_empty()
+        || type_name.is_empty()
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+
+    Some(Placeholder {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+    })
+}
+
+pub(super) fn next_is(chars: &mut Peekable<CharIndices>, expected: char) -> bool {
+    chars.next_if(|(_, c)| *c == expected).is_some()
+}
+
+/// Skips to the closing `quote`, which may be escaped with a backslash or by doubling it.
+pub(super) fn skip_quoted(chars: &mut Peekable<CharIndices>, quote: char) {
+    while let Some((_, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote && !next_is(chars, quote) {
+            return;
+        }
+    }
+}
+
+pub(super) fn skip_line(chars: &mut Peekable<CharIndices>) {
+    for (_, c) in chars.by_ref() {
+        if c == '\n' {
+            return;
+        }
+    }
+}
+
+/// ClickHouse block comments nest.
+pub(super) fn skip_block_comment(chars: &mut Peekable<CharIndices>) {
+    let mut depth = 1;
+    while let Some((_, c)) = chars.next() {
+        if c == '/' && next_is(chars, '*') {
+            depth += 1;
+        } else if c == '*' && next_is(chars, '/') {
+            depth -= 1;
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_schema::DataType;
+
+    use super::*;
+
+    fn placeholder(name: &str, type_name: &str) -> Placeholder {
+        Placeholder {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_placeholders_in_order() {
+        assert_eq!(
+            parse_placeholders("SELECT {b:UInt8}, { a : String } FROM t WHERE x = {b:UInt8}")
+                .unwrap(),
+            [placeholder("b", "UInt8"), placeholder("a", "String")]
+        );
+        assert_eq!(
+            parse_placeholders("SELECT {t:Array(Nullable(String))}").unwrap(),
+            [placeholder("t", "Array(Nullable(String))")]
+        );
+    }
+
+    #[test]
+    fn skips_literals_and_comments() {
+        let query = "SELECT '{a:String}', \"{b:String}\", `{c:String}`, 'it''s {d:String}', \
+                     'esc\\' {e:String}' -- {f:String}\n\
+                     # {g:String}\n\
+                     /* {h:String} /* nested */ {i:String} */ {j:String}";
+        assert_eq!(
+            parse_placeholders(query).unwrap(),
+            [placeholder("j", "String")]
+        );
+    }
+
+    #[test]
+    fn ignores_braces_that_are_not_placeholders() {
+        assert!(
+            parse_placeholders("SELECT {}, {a}, {:String}, {a b:String}, {a:}, map('{', 1)")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rejects_conflicting_types() {
+        assert_eq!(
+            parse_placeholders("SELECT {a:UInt8}, {a:String}")
+                .unwrap_err()
+                .status,
+            Status::InvalidArguments
+        );
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        assert_eq!(
+            substitute_placeholders(
+                "SELECT {a:UInt8}, '{b:String}' FROM {t:Identifier} WHERE s = {s:String}"
+            ),
+            "SELECT defaultValueOfTypeName('UInt8'), '{b:String}' FROM `t` \
+             WHERE s = defaultValueOfTypeName('String')"
+        );
+        assert_eq!(
+            substitute_placeholders("SELECT {d:DateTime64(3, 'UTC')}"),
+            "SELECT defaultValueOfTypeName('DateTime64(3, \\'UTC\\')')"
+        );
+        assert_eq!(substitute_placeholders("SELECT 1"), "SELECT 1");
+    }
+
+    #[test]
+    fn finds_identifier_placeholders() {
+        assert!(has_identifier_placeholders("SELECT * FROM {t:Identifier}"));
+        assert!(!has_identifier_placeholders(
+            "SELECT {a:UInt8} FROM t -- {t:Identifier}"
+        ));
+        assert!(!has_identifier_placeholders("SELECT '{t:Identifier}'"));
+    }
+
+    #[test]
+    fn builds_parameter_schema() {
+        let schema = parameter_schema("SELECT {a:UInt8}, {s:Nullable(String)}").unwrap();
+        assert_eq!(schema.field(0).name(), "a");
+        assert_eq!(schema.field(0).data_type(), &DataType::UInt8);
+        assert!(!schema.field(0).is_nullable());
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+        assert!(schema.field(1).is_nullable());
+
+        assert_eq!(
+            parameter_schema("SELECT {a:NoSuchType}")
+                .unwrap_err()
+                .status,
+            Status::InvalidArguments
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::str::FromStr;
+
+use adbc_core::error::{Error, Result, Status};
+use arrow_schema::{Field, Schema};
+use clickhouse_arrow::{ArrowOptions, Type, arrow::ch_to_arrow_type};
+
+/// What [`crate::ClickhouseStatement`] learned about its query when it was prepared.
+#[derive(Debug, Clone)]
+pub(crate) struct PreparedQuery {
+    /// The query text as it was prepared, executing anything else requires preparing again.
+    pub query: String,
+    /// The described schema of the rows the query returns, `None` unless it is a `SELECT` without
+    /// `Identifier` placeholders.
+    pub schema: Option<Schema>,
+    pub parameter_schema: Schema,
+}
+
+/// Whether `query` is a `SELECT` (or `WITH ... SELECT`) that can be described, as opposed to DDL
+/// and `INSERT`s which are only checked for syntax.
+pub(crate) fn returns_rows(query: &str) -> bool {
+    let keyword = first_keyword(query);
+    keyword.eq_ignore_ascii_case("SELECT") || keyword.eq_ignore_ascii_case("WITH")
+}
+
+/// Whether `query` is a `SHOW`, `DESCRIBE`, `EXPLAIN` or `EXISTS` statement. These return rows
+/// too but cannot be described, their schema is taken from the rows they return.
+pub(crate) fn is_introspection(query: &str) -> bool {
+    let keyword = first_keyword(query);
+    ["SHOW", "DESCRIBE", "DESC", "EXPLAIN", "EXISTS"]
+        .iter()
+        .any(|introspection| keyword.eq_ignore_ascii_case(introspection))
+}
+
+/// Whether `query` is an `INSERT`, which reports how many rows it wrote.
+pub(crate) fn is_insert(query: &str) -> bool {
+    first_keyword(query).eq_ignore_ascii_case("INSERT")
+}
+
+/// The first word of `query`, past leading comments and parentheses.
+fn first_keyword(query: &str) -> &str {
+    let mut rest = query;
+    loop {
+        let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+        if let Some(comment) = trimmed
+            .strip_prefix("--")
+            .or_else(|| trimmed.strip_prefix("# "))
+            .or_else(|| trimmed.strip_prefix("#!"))
+        {
+            rest = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = trimmed.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, rest)| rest);
+        } else {
+            rest = trimmed;
+            break;
+        }
+    }
+
+    rest.split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default()
+}
+
+/// How clients map ClickHouse types to Arrow. Result batches and [`result_schema`] both use it, so
+/// a described schema matches the batches the query returns.
+pub(crate) fn arrow_options() -> ArrowOptions {
+    ArrowOptions::default()
+}
+
+/// Converts described columns to the Arrow schema [`crate::reader::ClickhouseReader`] reports
+/// for the same query.
+pub(crate) fn result_schema(columns: Vec<(String, String)>) -> Result<Schema> {
+    let fields = columns
+        .into_iter()
+        .map(|(name, type_name)| {
+            let (data_type, nullable) = Type::from_str(&type_name)
+                .and_then(|ch_type| ch_to_arrow_type(&ch_type, Some(arrow_options())))
+                .map_err(|err| {
+                    Error::with_message_and_status(
+                        format!(
+                            "[Clickhouse] Unsupported type {type_name} of column {name}: {err}"
+                        ),
+                        Status::NotImplemented,
+                    )
+                })?;
+            Ok(Field::new(name, data_type, nullable))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Schema::new(fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_first_keyword() {
+        assert_eq!(first_keyword("  select 1"), "select");
+        assert_eq!(first_keyword("(SELECT 1) UNION ALL (SELECT 2)"), "SELECT");
+        assert_eq!(first_keyword("-- comment\n/* block */ WITH x AS 1"), "WITH");
+        assert_eq!(first_keyword("#! shebang\nSHOW TABLES"), "SHOW");
+        assert_eq!(first_keyword("/* unterminated"), "");
+    }
+
+    #[test]
+    fn classifies_queries() {
+        assert!(returns_rows("WITH 1 AS x SELECT x"));
+        assert!(!returns_rows("SHOW TABLES"));
+        assert!(!returns_rows("CREATE TABLE t (a Int8) ENGINE = Memory"));
+
+        for query in [
+            "SHOW TABLES",
+            "desc t",
+            "DESCRIBE TABLE t",
+            "EXPLAIN SELECT 1",
+            "EXISTS TABLE t",
+        ] {
+            assert!(is_introspection(query), "{query}");
+        }
+        assert!(!is_introspection("SELECT 1"));
+        assert!(!is_introspection("DESCRIPTION"));
+
+        assert!(is_insert("insert into t values (1)"));
+        assert!(!is_insert("SELECT 1"));
+    }
+
+    #[test]
+    fn converts_described_columns() {
+        let schema = result_schema(vec![
+            ("a".to_string(), "Int64".to_string()),
+            ("b".to_string(), "Nullable(String)".to_string()),
+        ])
+        .unwrap();
+        assert_eq!(schema.fields().len(), 2);
+        assert!(!schema.field(0).is_nullable());
+        assert!(schema.field(1).is_nullable());
+
+        let err = result_schema(vec![("c".to_string(), "NoSuchType".to_string())]).unwrap_err();
+        assert_eq!(err.status, Status::NotImplemented);
+    }
+}
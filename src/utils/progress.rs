@@ -0,0 +1,49 @@
+use std::pin::pin;
+
+use adbc_core::error::Result;
+use clickhouse_arrow::{ArrowClient, ClickHouseEvent, Event, Qid};
+use futures::future::{Either, select};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+/// Runs `fut` and sums the rows the server reports as written by query `qid` while it runs, or by
+/// any query of the client when there is no `qid`. The count is `None` when progress packets were
+/// missed, it would only be a lower bound then.
+pub(crate) async fn track_written_rows<T>(
+    client: &ArrowClient,
+    qid: Option<Qid>,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<(T, Option<u64>)> {
+    let mut events = client.subscribe_events();
+    let mut written_rows = 0;
+    let mut lagged = false;
+    let mut fut = pin!(fut);
+
+    let result = loop {
+        match select(fut.as_mut(), pin!(events.recv())).await {
+            Either::Left((result, _)) => break result?,
+            Either::Right((Ok(event), _)) => written_rows += event_written_rows(&event, qid),
+            Either::Right((Err(RecvError::Lagged(_)), _)) => lagged = true,
+            Either::Right((Err(RecvError::Closed), _)) => break fut.as_mut().await?,
+        }
+    };
+
+    // The last progress packets arrive right before the end of the query and may still be queued.
+    loop {
+        match events.try_recv() {
+            Ok(event) => written_rows += event_written_rows(&event, qid),
+            Err(TryRecvError::Lagged(_)) => lagged = true,
+            Err(_) => break,
+        }
+    }
+
+    Ok((result, (!lagged).then_some(written_rows)))
+}
+
+fn event_written_rows(event: &Event, qid: Option<Qid>) -> u64 {
+    match &event.event {
+        ClickHouseEvent::Progress(progress) if qid.is_none_or(|qid| qid == event.qid) => {
+            progress.written_rows.unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
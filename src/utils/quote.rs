@@ -0,0 +1,195 @@
+use adbc_core::error::{Error, Result, Status};
+
+/// Quotes an identifier with backticks so the server takes it literally, whatever dots, spaces,
+/// quotes or SQL it contains.
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("`{}`", name.replace('\\', "\\\\").replace('`', "\\`"))
+}
+
+/// A table reference, qualified with its database when there is one.
+pub(crate) fn quote_table(database: Option<&str>, table: &str) -> String {
+    match database {
+        Some(database) => format!("{}.{}", quote_identifier(database), quote_identifier(table)),
+        None => quote_identifier(table),
+    }
+}
+
+/// Splits a user supplied `db.table` name into its database and table.
+///
+/// An unquoted name is split at its dot. Either part may be quoted with backticks or double
+/// quotes, escaping quotes by doubling them or with a backslash, to keep dots in it: both
+/// `` `my.table` `` and `"my.table"` name a table in the current database.
+pub(crate) fn parse_table_name(name: &str) -> Result<(Option<String>, String)> {
+    let mut parts = vec![];
+    let mut chars = name.chars().peekable();
+
+    loop {
+        let mut part = String::new();
+        match chars.peek() {
+            Some(&quote) if quote == '`' || quote == '"' => {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('\\') => part.extend(chars.next()),
+                        Some(c) if c == quote => {
+                            if chars.next_if_eq(&quote).is_none() {
+                                break;
+                            }
+                            part.push(quote);
+                        }
+                        Some(c) => part.push(c),
+                        None => return Err(invalid_table_name(name)),
+                    }
+                }
+            }
+            _ => {
+                while let Some(c) = chars.next_if(|c| *c != '.') {
+                    part.push(c);
+                }
+            }
+        }
+
+        if part.is_empty() {
+            return Err(invalid_table_name(name));
+        }
+        parts.push(part);
+
+        match chars.next() {
+            Some('.') => {}
+            None => break,
+            Some(_) => return Err(invalid_table_name(name)),
+        }
+    }
+
+    match <[String; 2]>::try_from(parts) {
+        Ok([database, table]) => Ok((Some(database), table)),
+        Err(mut parts) if parts.len() == 1 => Ok((None, parts.remove(0))),
+        Err(_) => Err(invalid_table_name(name)),
+    }
+}
+
+fn invalid_table_name(name: &str) -> Error {
+    Error::with_message_and_status(
+        format!(
+            "[Clickhouse] Invalid table name: {name}, expected table or db.table, quote names containing dots with backticks"
+        ),
+        Status::InvalidArguments,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_hostile_identifiers() {
+        assert_eq!(quote_identifier("plain"), "`plain`");
+        assert_eq!(quote_identifier("a`b"), "`a\\`b`");
+        assert_eq!(quote_identifier("a\\`b"), "`a\\\\\\`b`");
+        assert_eq!(quote_identifier("trailing\\"), "`trailing\\\\`");
+        assert_eq!(quote_identifier("db.table"), "`db.table`");
+        assert_eq!(quote_identifier("with space"), "`with space`");
+        assert_eq!(
+            quote_identifier("x`; DROP TABLE users; --"),
+            "`x\\`; DROP TABLE users; --`"
+        );
+        assert_eq!(quote_identifier(""), "``");
+    }
+
+    #[test]
+    fn quotes_tables() {
+        assert_eq!(quote_table(None, "t"), "`t`");
+        assert_eq!(quote_table(Some("db"), "t"), "`db`.`t`");
+        assert_eq!(quote_table(Some("my.db"), "a`b"), "`my.db`.`a\\`b`");
+    }
+
+    #[test]
+    fn parses_unquoted_names() {
+        assert_eq!(parse_table_name("t").unwrap(), (None, "t".to_string()));
+        assert_eq!(
+            parse_table_name("db.t").unwrap(),
+            (Some("db".to_string()), "t".to_string())
+        );
+        assert_eq!(
+            parse_table_name("with space").unwrap(),
+            (None, "with space".to_string())
+        );
+        assert_eq!(
+            parse_table_name("t; DROP TABLE users").unwrap(),
+            (None, "t; DROP TABLE users".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_quoted_names() {
+        assert_eq!(
+            parse_table_name("`my.table`").unwrap(),
+            (None, "my.table".to_string())
+        );
+        assert_eq!(
+            parse_table_name("\"my.table\"").unwrap(),
+            (None, "my.table".to_string())
+        );
+        assert_eq!(
+            parse_table_name("`my.db`.\"my.table\"").unwrap(),
+            (Some("my.db".to_string()), "my.table".to_string())
+        );
+        assert_eq!(
+            parse_table_name("db.`t`").unwrap(),
+            (Some("db".to_string()), "t".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_escaped_quotes() {
+        assert_eq!(
+            parse_table_name("`a``b`").unwrap(),
+            (None, "a`b".to_string())
+        );
+        assert_eq!(
+            parse_table_name("`a\\`b`").unwrap(),
+            (None, "a`b".to_string())
+        );
+        assert_eq!(
+            parse_table_name("\"a\"\"b\"").unwrap(),
+            (None, "a\"b".to_string())
+        );
+        assert_eq!(
+            parse_table_name("\"a\\\"b\"").unwrap(),
+            (None, "a\"b".to_string())
+        );
+        assert_eq!(
+            parse_table_name("`a\\\\`").unwrap(),
+            (None, "a\\".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        for name in [
+            "a.b.c",
+            "`a`.`b`.`c`",
+            "",
+            ".t",
+            "db.",
+            "``",
+            "db..t",
+            "`unclosed",
+            "\"unclosed",
+            "`a`b",
+        ] {
+            let err = parse_table_name(name).unwrap_err();
+            assert_eq!(err.status, Status::InvalidArguments, "{name}");
+        }
+    }
+
+    #[test]
+    fn round_trips_through_quoting() {
+        for name in ["a`b", "a\\b", "a.b", "x`; DROP TABLE users; --"] {
+            assert_eq!(
+                parse_table_name(&quote_identifier(name)).unwrap(),
+                (None, name.to_string())
+            );
+        }
+    }
+}
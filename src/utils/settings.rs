@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use adbc_core::error::{Error, Result, Status};
+
+use crate::{
+    consts::SETTING_OPTION_PREFIX,
+    utils::placeholders::{next_is, skip_block_comment, skip_line, skip_quoted},
+};
+
+/// ClickHouse settings keyed by name, as collected from `clickhouse.setting.<name>` options.
+pub(crate) type SettingsMap = BTreeMap<String, String>;
+
+/// Extracts the setting name from a `clickhouse.setting.<name>` option key, returning `None` for
+/// keys outside of the namespace.
+pub(crate) fn setting_name(key: &str) -> Option<Result<&str>> {
+    let name = key.strip_prefix(SETTING_OPTION_PREFIX)?;
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Some(Err(Error::with_message_and_status(
+            format!("[Clickhouse] Invalid setting name: {key}"),
+            Status::InvalidArguments,
+        )));
+    }
+
+    Some(Ok(name))
+}
+
+/// Renders a setting value as a SQL literal: numbers and booleans are passed through, anything
+/// else becomes a quoted string.
+pub(crate) fn setting_literal(value: &str) -> String {
+    if value.parse::<i64>().is_ok()
+        || value.parse::<f64>().is_ok_and(f64::is_finite)
+        || value == "true"
+        || value == "false"
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Whether `query` changes the session it runs in: `SET`, `USE` and the statements on temporary
+/// tables, whose tables live as long as the session.
+pub(crate) fn changes_session(query: &str) -> bool {
+    let words = words(query);
+    let is = |index: usize, keywords: &[&str]| {
+        words.get(index).is_some_and(|(_, word, _)| {
+            keywords
+                .iter()
+                .any(|keyword| word.eq_ignore_ascii_case(keyword))
+        })
+    };
+
+    is(0, &["SET", "USE"]) || is(1, &["TEMPORARY"])
+}
+
+/// The words of `query` with their offset and parenthesis depth, leaving out literals, quoted
+/// identifiers, comments and the parts of qualified names like `system.settings`.
+fn words(query: &str) -> Vec<(usize, &str, usize)> {
+    let mut words = vec![];
+    let mut chars = query.char_indices().peekable();
+    let mut previous = ' ';
+    let mut depth = 0usize;
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => skip_quoted(&mut chars, c),
+            '-' if next_is(&mut chars, '-') => skip_line(&mut chars),
+            '#' if chars.peek().is_some_and(|(_, c)| *c == ' ' || *c == '!') => {
+                skip_line(&mut chars)
+            }
+            '/' if next_is(&mut chars, '*') => skip_block_comment(&mut chars),
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + 1;
+                while let Some((idx, _)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    end = idx + 1;
+                }
+                if previous != '.' {
+                    words.push((start, &query[start..end], depth));
+                }
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_statements_changing_the_session() {
+        assert!(changes_session("SET max_threads = 1"));
+        assert!(changes_session("-- switch\nuse db"));
+        assert!(changes_session("CREATE TEMPORARY TABLE t (a Int32)"));
+        assert!(changes_session("DROP TEMPORARY TABLE IF EXISTS t"));
+        assert!(!changes_session("SELECT 'SET'"));
+        assert!(!changes_session(
+            "CREATE TABLE temporary (a Int32) ENGINE = Memory"
+        ));
+        assert!(!changes_session(
+            "INSERT INTO t SETTINGS async_insert = 1 VALUES (1)"
+        ));
+    }
+}
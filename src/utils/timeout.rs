@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use adbc_core::{
+    error::{Error, Result, Status},
+    options::OptionValue,
+};
+use tokio::time::Instant;
+
+/// Parses a `*_timeout_ms` option given as a string, integer or double number of milliseconds.
+/// Zero disables the timeout.
+pub(crate) fn parse_timeout_option(key: &str, value: OptionValue) -> Result<Option<Duration>> {
+    parse_duration_option(key, value).map(|timeout| Some(timeout).filter(|t| !t.is_zero()))
+}
+
+/// Like [`parse_timeout_option`] but keeps zero, for timeouts which override another one.
+pub(crate) fn parse_duration_option(key: &str, value: OptionValue) -> Result<Duration> {
+    let millis = match value {
+        OptionValue::String(value) => value.parse::<f64>().ok(),
+        OptionValue::Int(value) => Some(value as f64),
+        OptionValue::Double(value) => Some(value),
+        _ => None,
+    };
+
+    millis
+        .filter(|millis| millis.is_finite() && *millis >= 0.0)
+        .and_then(|millis| Duration::try_from_secs_f64(millis / 1000.0).ok())
+        .ok_or_else(|| {
+            Error::with_message_and_status(
+                format!("[Clickhouse] Invalid value for {key}, expected a non-negative number of milliseconds"),
+                Status::InvalidArguments,
+            )
+        })
+}
+
+pub(crate) fn timeout_millis(timeout: Option<Duration>) -> u128 {
+    timeout.map_or(0, |timeout| timeout.as_millis())
+}
+
+/// Fails with `Status::Timeout` once `deadline` has passed, or waits for `fut` forever when there
+/// is none.
+pub(crate) async fn with_deadline<T>(
+    deadline: Option<Instant>,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, fut)
+            .await
+            .unwrap_or_else(|_| {
+                Err(Error::with_message_and_status(
+                    "[Clickhouse] Query timed out",
+                    Status::Timeout,
+                ))
+            }),
+        None => fut.await,
+    }
+}
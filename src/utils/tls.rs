@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use adbc_core::error::{Error, Result, Status};
+use tokio_rustls::rustls::{
+    self, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject},
+};
+
+use crate::consts::{
+    DATABASE_OPTION_TLS_CA_FILE, DATABASE_OPTION_TLS_CLIENT_CERT, DATABASE_OPTION_TLS_CLIENT_KEY,
+    DATABASE_OPTION_TLS_ENABLED,
+};
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TlsOptions {
+    pub enabled: Option<bool>,
+    pub server_name: Option<String>,
+    pub ca_file: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub insecure_skip_verify: bool,
+}
+
+impl TlsOptions {
+    /// Whether to connect over TLS. Unless it is turned off explicitly, any TLS material turns it
+    /// on.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or_else(|| self.has_material())
+    }
+
+    /// Whether a custom CA, a client certificate or skipped verification is configured.
+    fn has_material(&self) -> bool {
+        self.ca_file.is_some()
+            || self.client_cert.is_some()
+            || self.client_key.is_some()
+            || self.insecure_skip_verify
+    }
+
+    /// The rustls configuration for a custom CA, a client certificate or skipped verification,
+    /// `None` when `clickhouse_arrow`'s default of trusting the webpki roots applies.
+    pub fn client_config(&self) -> Result<Option<Arc<ClientConfig>>> {
+        if !self.has_material() {
+            return Ok(None);
+        }
+        if !self.is_enabled() {
+            return Err(Error::with_message_and_status(
+                format!(
+                    "[Clickhouse] TLS options are set but TLS is turned off by {DATABASE_OPTION_TLS_ENABLED} or a clickhouse:// uri"
+                ),
+                Status::InvalidArguments,
+            ));
+        }
+
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+
+        let builder = if self.insecure_skip_verify {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+        } else {
+            builder.with_root_certificates(self.root_store()?)
+        };
+
+        let config = match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let certs = read_certs(DATABASE_OPTION_TLS_CLIENT_CERT, cert)?;
+                let key = PrivateKeyDer::from_pem_file(key)
+                    .map_err(|err| read_error(DATABASE_OPTION_TLS_CLIENT_KEY, key, err))?;
+                builder
+                    .with_client_auth_cert(certs, key)
+                    .map_err(tls_error)?
+            }
+            (None, None) => builder.with_no_client_auth(),
+            _ => {
+                return Err(Error::with_message_and_status(
+                    format!(
+                        "[Clickhouse] {DATABASE_OPTION_TLS_CLIENT_CERT} and {DATABASE_OPTION_TLS_CLIENT_KEY} must be set together"
+                    ),
+                    Status::InvalidArguments,
+                ));
+            }
+        };
+
+        Ok(Some(Arc::new(config)))
+    }
+
+    /// The certificates of `clickhouse.tls.ca_file` if set, the webpki roots otherwise.
+    fn root_store(&self) -> Result<RootCertStore> {
+        let Some(ca_file) = &self.ca_file else {
+            return Ok(RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+            });
+        };
+
+        let mut roots = RootCertStore::empty();
+        for cert in read_certs(DATABASE_OPTION_TLS_CA_FILE, ca_file)? {
+            roots.add(cert).map_err(tls_error)?;
+        }
+        Ok(roots)
+    }
+}
+
+fn read_certs(key: &str, path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|err| read_error(key, path, err))?;
+    if certs.is_empty() {
+        return Err(read_error(key, path, "no certificate found"));
+    }
+    Ok(certs)
+}
+
+fn read_error(key: &str, path: &str, err: impl std::fmt::Display) -> Error {
+    Error::with_message_and_status(
+        format!("[Clickhouse] Failed to read {key} {path}: {err}"),
+        Status::InvalidArguments,
+    )
+}
+
+fn tls_error(err: rustls::Error) -> Error {
+    Error::with_message_and_status(
+        format!("[Clickhouse] Invalid TLS configuration: {err}"),
+        Status::InvalidArguments,
+    )
+}
+
+/// Accepts any server certificate for `clickhouse.tls.insecure_skip_verify`, the signatures of
+/// the handshake are still checked.
+#[derive(Debug)]
+struct SkipServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}